@@ -1,8 +1,10 @@
 use bevy::prelude::MessageReader;
 
 use crate::{
-    DeleteMessage, InsertMessage, InsertUpdateMessage, ReducerResultMessage, StdbConnectedMessage,
-    StdbConnectionErrorMessage, StdbDisconnectedMessage, UpdateMessage,
+    DeleteMessage, InsertMessage, InsertUpdateMessage, ProcedureResultMessage,
+    ReducerResultMessage, StdbConnectedMessage, StdbConnectionErrorMessage,
+    StdbDisconnectedMessage, StdbReconnectedMessage, StdbReconnectingMessage,
+    SubscriptionAppliedMessage, SubscriptionErrorMessage, TransactionMessage, UpdateMessage,
 };
 
 /// A type alias for a Bevy message reader for InsertMessage<T>.
@@ -20,11 +22,36 @@ pub type ReadInsertUpdateMessage<'w, 's, T> = MessageReader<'w, 's, InsertUpdate
 /// A type alias for a Bevy message reader for ReducerResultMessage<T>.
 pub type ReadReducerMessage<'w, 's, T> = MessageReader<'w, 's, ReducerResultMessage<T>>;
 
+/// A type alias for a Bevy message reader for ProcedureResultMessage<T>.
+pub type ReadProcedureMessage<'w, 's, T> = MessageReader<'w, 's, ProcedureResultMessage<T>>;
+
 /// A type alias for a Bevy message reader for StdbConnectedMessage.
-pub type ReadStdbConnectedMessage<'w, 's> = MessageReader<'w, 's, StdbConnectedMessage>;
+pub type ReadStdbConnectedMessage<'w, 's, L = ()> = MessageReader<'w, 's, StdbConnectedMessage<L>>;
 
 /// A type alias for a Bevy message reader for StdbDisconnectedMessage.
-pub type ReadStdbDisconnectedMessage<'w, 's> = MessageReader<'w, 's, StdbDisconnectedMessage>;
+pub type ReadStdbDisconnectedMessage<'w, 's, L = ()> =
+    MessageReader<'w, 's, StdbDisconnectedMessage<L>>;
 
 /// A type alias for a Bevy message reader for StdbConnectionErrorMessage.
-pub type ReadStdbConnectionErrorMessage<'w, 's> = MessageReader<'w, 's, StdbConnectionErrorMessage>;
+pub type ReadStdbConnectionErrorMessage<'w, 's, L = ()> =
+    MessageReader<'w, 's, StdbConnectionErrorMessage<L>>;
+
+/// A type alias for a Bevy message reader for StdbReconnectingMessage.
+pub type ReadStdbReconnectingMessage<'w, 's, L = ()> =
+    MessageReader<'w, 's, StdbReconnectingMessage<L>>;
+
+/// A type alias for a Bevy message reader for StdbReconnectedMessage.
+pub type ReadStdbReconnectedMessage<'w, 's, L = ()> =
+    MessageReader<'w, 's, StdbReconnectedMessage<L>>;
+
+/// A type alias for a Bevy message reader for SubscriptionAppliedMessage.
+pub type ReadSubscriptionAppliedMessage<'w, 's, L = ()> =
+    MessageReader<'w, 's, SubscriptionAppliedMessage<L>>;
+
+/// A type alias for a Bevy message reader for SubscriptionErrorMessage.
+pub type ReadSubscriptionErrorMessage<'w, 's, L = ()> =
+    MessageReader<'w, 's, SubscriptionErrorMessage<L>>;
+
+/// A type alias for a Bevy message reader for TransactionMessage<R>.
+pub type ReadTransactionMessage<'w, 's, R, L = ()> =
+    MessageReader<'w, 's, TransactionMessage<R, L>>;
@@ -2,12 +2,14 @@
 // This introduces message channels, on one side of which is mpsc::Sender<T>, and on another
 // side is bevy's MessageReader<T>, and it automatically bridges between the two.
 
+use bevy::ecs::message::MessageRegistry;
+use bevy::ecs::schedule::Schedules;
 use bevy::prelude::*;
 use std::sync::Mutex;
 use std::sync::mpsc::Receiver;
 
 #[derive(Resource, Deref, DerefMut)]
-struct ChannelReceiver<T>(Mutex<Receiver<T>>);
+pub(crate) struct ChannelReceiver<T>(Mutex<Receiver<T>>);
 
 /// Allows to register a message channel backed by a `mpsc::Receiver<T>`.
 /// This is useful in multithreaded applications where you want to send messages from a different thread
@@ -30,7 +32,24 @@ impl AddMessageChannelAppExtensions for App {
     }
 }
 
-fn channel_to_message<T: 'static + Send + Sync + Message>(
+impl AddMessageChannelAppExtensions for World {
+    fn add_message_channel<T: Message>(&mut self, receiver: Receiver<T>) -> &mut Self {
+        assert!(
+            !self.contains_resource::<ChannelReceiver<T>>(),
+            "this SpacetimeDB message channel is already initialized",
+        );
+
+        if !self.contains_resource::<Messages<T>>() {
+            MessageRegistry::register_message::<T>(self);
+        }
+        self.resource_mut::<Schedules>()
+            .add_systems(PreUpdate, channel_to_message::<T>);
+        self.insert_resource(ChannelReceiver(Mutex::new(receiver)));
+        self
+    }
+}
+
+pub(crate) fn channel_to_message<T: 'static + Send + Sync + Message>(
     receiver: Res<ChannelReceiver<T>>,
     mut writer: MessageWriter<T>,
 ) {
@@ -0,0 +1,80 @@
+use bevy::{ecs::world::World, prelude::Commands};
+use spacetimedb_sdk::{__codegen as spacetime_codegen, DbContext};
+
+use crate::{
+    StdbConnectRequest, connect_labeled, connect_with_request, connect_with_token, disconnect,
+    disconnect_labeled,
+};
+
+/// Extends [`Commands`] to open and close the connection of [`crate::StdbPlugin`] from any system.
+///
+/// ```ignore
+/// fn on_login(mut commands: Commands, auth: Res<Auth>) {
+///     commands.stdb_connect::<DbConnection, RemoteModule>(Some(auth.token.clone()));
+/// }
+/// ```
+pub trait StdbCommandsExt {
+    /// Connects with the given token, see [`connect_with_token`].
+    fn stdb_connect<C, M>(&mut self, token: Option<String>)
+    where
+        C: spacetime_codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+        M: spacetime_codegen::SpacetimeModule<DbConnection = C>;
+
+    /// Connects with the given request, see [`connect_with_request`].
+    fn stdb_connect_with<C, M>(&mut self, request: StdbConnectRequest)
+    where
+        C: spacetime_codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+        M: spacetime_codegen::SpacetimeModule<DbConnection = C>;
+
+    /// Closes the connection without reconnecting, see [`disconnect`].
+    fn stdb_disconnect<C: DbContext + Send + Sync + 'static>(&mut self);
+
+    /// Connects the plugin with the label `L` with the given request, see [`connect_labeled`].
+    fn stdb_connect_labeled<C, M, L>(&mut self, request: StdbConnectRequest<L>)
+    where
+        C: spacetime_codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+        M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
+        L: Send + Sync + 'static;
+
+    /// Closes the connection of the plugin with the label `L`, see [`disconnect_labeled`].
+    fn stdb_disconnect_labeled<C: DbContext + Send + Sync + 'static, L: Send + Sync + 'static>(
+        &mut self,
+    );
+}
+
+impl StdbCommandsExt for Commands<'_, '_> {
+    fn stdb_connect<C, M>(&mut self, token: Option<String>)
+    where
+        C: spacetime_codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+        M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
+    {
+        self.queue(move |world: &mut World| connect_with_token::<C, M>(world, token));
+    }
+
+    fn stdb_connect_with<C, M>(&mut self, request: StdbConnectRequest)
+    where
+        C: spacetime_codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+        M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
+    {
+        self.queue(move |world: &mut World| connect_with_request::<C, M>(world, request));
+    }
+
+    fn stdb_disconnect<C: DbContext + Send + Sync + 'static>(&mut self) {
+        self.queue(disconnect::<C>);
+    }
+
+    fn stdb_connect_labeled<C, M, L>(&mut self, request: StdbConnectRequest<L>)
+    where
+        C: spacetime_codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+        M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
+        L: Send + Sync + 'static,
+    {
+        self.queue(move |world: &mut World| connect_labeled::<C, M, L>(world, request));
+    }
+
+    fn stdb_disconnect_labeled<C: DbContext + Send + Sync + 'static, L: Send + Sync + 'static>(
+        &mut self,
+    ) {
+        self.queue(disconnect_labeled::<C, L>);
+    }
+}
@@ -0,0 +1,348 @@
+use std::{hash::Hash, marker::PhantomData, sync::Arc};
+
+use bevy::{
+    app::PreUpdate,
+    ecs::{message::Message, schedule::Schedules, world::World},
+    platform::collections::HashMap,
+    prelude::{Bundle, Commands, Entity, IntoScheduleConfigs, MessageReader, ResMut, Resource},
+};
+use spacetimedb_sdk::__codegen as spacetime_codegen;
+
+use crate::{
+    EntityRowDeleted, EntityRowInserted, EntityRowUpdated, StdbPlugin, TableWithPrimaryKeyAccessor,
+    channel_receiver::channel_to_message,
+    stdb_connection::UnregisterFn,
+    tables::{RowMap, TableSenders},
+};
+
+/// Maps the primary key of the rows of a table mirrored with [`StdbPlugin::add_table_as_entities`]
+/// to the entity holding them.
+#[derive(Resource)]
+pub struct TableEntities<TRow, K> {
+    entities: HashMap<K, Entity>,
+    _phantom: PhantomData<fn() -> TRow>,
+}
+
+impl<TRow, K> Default for TableEntities<TRow, K> {
+    fn default() -> Self {
+        Self {
+            entities: HashMap::default(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<TRow, K: Eq + Hash> TableEntities<TRow, K> {
+    /// Returns the entity of the row with the given primary key.
+    pub fn get(&self, key: &K) -> Option<Entity> {
+        self.entities.get(key).copied()
+    }
+
+    /// Iterates over the primary keys and entities of all mirrored rows.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, Entity)> {
+        self.entities.iter().map(|(key, entity)| (key, *entity))
+    }
+
+    /// Number of mirrored rows.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Whether no rows are mirrored.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+/// A row change forwarded from the SpacetimeDB thread to the system mirroring the table.
+pub(crate) enum RowChange<TRow> {
+    Insert(TRow),
+    Update(TRow, TRow),
+    Delete(TRow),
+    /// The connection the rows came from was dropped, sent after its last row.
+    Clear,
+}
+
+pub(crate) struct RowChangeMessage<TRow>(pub(crate) RowChange<TRow>);
+
+impl<TRow: Send + Sync + 'static> Message for RowChangeMessage<TRow> {}
+
+impl<
+    C: spacetime_codegen::DbConnection<Module = M> + spacetimedb_sdk::DbContext,
+    M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
+    L: Send + Sync + 'static,
+> StdbPlugin<C, M, L>
+{
+    /// Mirrors the rows of a table into entities holding the row as a component.
+    ///
+    /// Entities are spawned on insert, updated on update and despawned on delete.
+    /// `key_fn` returns the primary key of a row, the entity of a row can be found in the
+    /// [`TableEntities<TRow, K>`] resource.
+    ///
+    /// The SDK doesn't report the rows deleted while disconnected, so all the entities are despawned
+    /// when the connection is closed or replaced, and spawned again from the rows of the next connection.
+    ///
+    /// [`EntityRowInserted`], [`EntityRowUpdated`] and [`EntityRowDeleted`] are triggered on the entities,
+    /// for use with entity observers.
+    ///
+    /// A table can be mirrored only once, by a single plugin: the plugin panics when it is built if the rows
+    /// with the same key are already mirrored.
+    pub fn add_table_as_entities<TRow, A, K>(
+        self,
+        accessor: A,
+        key_fn: impl Fn(&TRow) -> K + Send + Sync + 'static,
+    ) -> Self
+    where
+        TRow: bevy::prelude::Component + Clone,
+        A: TableWithPrimaryKeyAccessor<C::DbView, Row = TRow, EventContext = M::EventContext>,
+        K: Eq + Hash + Send + Sync + 'static,
+    {
+        self.add_table_as_bundles::<TRow, TRow, A, K>(accessor, key_fn)
+    }
+
+    /// Same as [`StdbPlugin::add_table_as_entities`], but the entities hold the bundle `B` built from the row.
+    ///
+    /// The table can't also be added with another bundle, see [`StdbPlugin::add_table_as_entities`].
+    ///
+    /// ```ignore
+    /// plugin.add_table_as_bundles::<PlayerBundle, _, _, _>(RemoteTables::player, |player| player.id)
+    /// ```
+    pub fn add_table_as_bundles<B, TRow, A, K>(
+        self,
+        accessor: A,
+        key_fn: impl Fn(&TRow) -> K + Send + Sync + 'static,
+    ) -> Self
+    where
+        B: Bundle + From<TRow>,
+        TRow: Send + Sync + Clone + 'static,
+        A: TableWithPrimaryKeyAccessor<C::DbView, Row = TRow, EventContext = M::EventContext>,
+        K: Eq + Hash + Send + Sync + 'static,
+    {
+        let key_fn = Arc::new(key_fn);
+
+        // A closure that registers the row changes, the index and the system mirroring them when the plugin is built
+        let register_messages = move |plugin: &Self, world: &mut World| {
+            TableSenders::<TRow>::row_changes(plugin, world).register(world);
+
+            // A second index would mirror the same rows into other entities
+            assert!(
+                !world.contains_resource::<TableEntities<TRow, K>>(),
+                "The rows of {} are already mirrored into entities, add their table as entities only once",
+                std::any::type_name::<TRow>()
+            );
+            world.init_resource::<TableEntities<TRow, K>>();
+            let key_fn = Arc::clone(&key_fn);
+            world.resource_mut::<Schedules>().add_systems(
+                PreUpdate,
+                (move |commands: Commands,
+                       changes: MessageReader<RowChangeMessage<TRow>>,
+                       index: ResMut<TableEntities<TRow, K>>| {
+                    sync_table_entities::<B, TRow, K>(commands, changes, index, &*key_fn)
+                })
+                .after(channel_to_message::<RowChangeMessage<TRow>>),
+            );
+        };
+        self.message_registers
+            .lock()
+            .unwrap()
+            .push(Box::new(register_messages));
+
+        // A closure that forwards the table callbacks to the row changes, on every new connection,
+        // and returns the closure removing them when the connection is dropped
+        let accessor = Arc::new(accessor);
+        let register = move |world: &World, db: &C::DbView| {
+            let senders = TableSenders::<TRow>::registered_row_changes(world);
+            let unregister_update = senders.clone().on_update(&*accessor, db, RowMap::all());
+            let unregister_insert_delete =
+                senders
+                    .clone()
+                    .on_insert_delete(&*accessor, db, RowMap::all());
+
+            let accessor = Arc::clone(&accessor);
+            Box::new(move |db: &C::DbView| {
+                unregister_update(&*accessor, db);
+                unregister_insert_delete(&*accessor, db);
+                senders.clear_row_changes();
+            }) as UnregisterFn<C>
+        };
+        self.table_registers
+            .lock()
+            .unwrap()
+            .push(Box::new(register));
+
+        self
+    }
+}
+
+/// Applies the row changes received since the last frame to the mirrored entities.
+fn sync_table_entities<B, TRow, K>(
+    mut commands: Commands,
+    mut changes: MessageReader<RowChangeMessage<TRow>>,
+    mut index: ResMut<TableEntities<TRow, K>>,
+    key_fn: &dyn Fn(&TRow) -> K,
+) where
+    B: Bundle + From<TRow>,
+    TRow: Send + Sync + Clone + 'static,
+    K: Eq + Hash + Send + Sync + 'static,
+{
+    for RowChangeMessage(change) in changes.read() {
+        match change {
+            RowChange::Insert(row) => {
+                let entity =
+                    upsert_entity(&mut commands, &mut index, key_fn(row), B::from(row.clone()));
+                commands.trigger(EntityRowInserted {
+                    entity,
+                    row: row.clone(),
+                });
+            }
+            RowChange::Update(old, new) => {
+                let entity =
+                    upsert_entity(&mut commands, &mut index, key_fn(new), B::from(new.clone()));
+                commands.trigger(EntityRowUpdated {
+                    entity,
+                    old: old.clone(),
+                    new: new.clone(),
+                });
+            }
+            RowChange::Delete(row) => {
+                if let Some(entity) = index.entities.remove(&key_fn(row)) {
+                    commands.trigger(EntityRowDeleted {
+                        entity,
+                        row: row.clone(),
+                    });
+                    commands.entity(entity).try_despawn();
+                }
+            }
+            RowChange::Clear => {
+                for (_, entity) in index.entities.drain() {
+                    commands.entity(entity).try_despawn();
+                }
+            }
+        }
+    }
+}
+
+/// Inserts the bundle on the entity of the row with the given key, spawning it if needed.
+fn upsert_entity<B: Bundle, TRow, K: Eq + Hash>(
+    commands: &mut Commands,
+    index: &mut TableEntities<TRow, K>,
+    key: K,
+    bundle: B,
+) -> Entity {
+    // Reuse the entity of the row unless it was despawned by the app
+    if let Some(entity) = index.entities.get(&key).copied()
+        && let Ok(mut entity_commands) = commands.get_entity(entity)
+    {
+        entity_commands.insert(bundle);
+        return entity;
+    }
+
+    let entity = commands.spawn(bundle).id();
+    index.entities.insert(key, entity);
+    entity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bindings::Player;
+    use bevy::{
+        ecs::{message::Messages, system::RunSystemOnce},
+        prelude::Component,
+    };
+
+    #[derive(Component)]
+    struct Name(String);
+
+    impl From<Player> for Name {
+        fn from(player: Player) -> Self {
+            Name(player.name)
+        }
+    }
+
+    fn player(id: u64, name: &str) -> Player {
+        Player {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    fn sync(world: &mut World, changes: impl IntoIterator<Item = RowChange<Player>>) {
+        for change in changes {
+            world.write_message(RowChangeMessage(change));
+        }
+        world
+            .run_system_once(
+                |commands: Commands,
+                 changes: MessageReader<RowChangeMessage<Player>>,
+                 index: ResMut<TableEntities<Player, u64>>| {
+                    sync_table_entities::<Name, _, _>(commands, changes, index, &|player| player.id)
+                },
+            )
+            .unwrap();
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Messages<RowChangeMessage<Player>>>();
+        world.init_resource::<TableEntities<Player, u64>>();
+        world
+    }
+
+    #[test]
+    fn rows_are_mirrored_into_entities() {
+        let mut world = world();
+        sync(
+            &mut world,
+            [
+                RowChange::Insert(player(1, "Alice")),
+                RowChange::Insert(player(2, "Bob")),
+            ],
+        );
+        let alice = world
+            .resource::<TableEntities<Player, u64>>()
+            .get(&1)
+            .unwrap();
+        assert_eq!(world.get::<Name>(alice).unwrap().0, "Alice");
+
+        sync(
+            &mut world,
+            [
+                RowChange::Update(player(1, "Alice"), player(1, "Carol")),
+                RowChange::Delete(player(2, "Bob")),
+            ],
+        );
+        let index = world.resource::<TableEntities<Player, u64>>();
+        assert_eq!(index.get(&1), Some(alice));
+        assert_eq!(index.len(), 1);
+        assert_eq!(world.get::<Name>(alice).unwrap().0, "Carol");
+    }
+
+    #[test]
+    fn dropping_the_connection_despawns_the_entities() {
+        let mut world = world();
+        sync(
+            &mut world,
+            [
+                RowChange::Insert(player(1, "Alice")),
+                RowChange::Insert(player(2, "Bob")),
+            ],
+        );
+        let bob = world
+            .resource::<TableEntities<Player, u64>>()
+            .get(&2)
+            .unwrap();
+
+        // Bob was deleted while disconnected, only Alice is inserted by the next connection
+        sync(
+            &mut world,
+            [RowChange::Clear, RowChange::Insert(player(1, "Alice"))],
+        );
+        let index = world.resource::<TableEntities<Player, u64>>();
+        assert_eq!(index.len(), 1);
+        assert!(index.get(&2).is_none());
+        let alice = index.get(&1).unwrap();
+        assert!(world.get_entity(bob).is_err());
+        assert_eq!(world.get::<Name>(alice).unwrap().0, "Alice");
+    }
+}
@@ -0,0 +1,167 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    app::PreUpdate,
+    ecs::{schedule::Schedules, world::World},
+    prelude::{Commands, Entity, EntityEvent, Event, IntoScheduleConfigs, Resource},
+};
+
+use crate::{
+    DeleteMessage, InsertMessage, ReadDeleteMessage, ReadInsertMessage, ReadUpdateMessage,
+    StdbEventInfo, TableMessages, UpdateMessage, channel_receiver::channel_to_message,
+};
+
+/// An event that is triggered when a row is inserted into a table registered with [`crate::TableMessages::triggers`].
+#[derive(Event, Debug)]
+pub struct RowInserted<T> {
+    /// The row that was inserted.
+    pub row: T,
+    /// The event that inserted the row.
+    pub event: StdbEventInfo,
+}
+
+impl<T> RowInserted<T> {
+    /// Same as [`InsertMessage::is_initial`].
+    pub fn is_initial(&self) -> bool {
+        self.event == StdbEventInfo::SubscribeApplied
+    }
+}
+
+/// An event that is triggered when a row is updated in a table registered with [`crate::TableMessages::triggers`].
+#[derive(Event, Debug)]
+pub struct RowUpdated<T> {
+    /// The old row.
+    pub old: T,
+    /// The new row.
+    pub new: T,
+    /// The event that updated the row.
+    pub event: StdbEventInfo,
+}
+
+/// An event that is triggered when a row is deleted from a table registered with [`crate::TableMessages::triggers`].
+#[derive(Event, Debug)]
+pub struct RowDeleted<T> {
+    /// The row that was deleted.
+    pub row: T,
+    /// The event that deleted the row.
+    pub event: StdbEventInfo,
+}
+
+/// An event that is triggered on the entity mirroring a row, after the row was inserted.
+///
+/// Only triggered for tables registered with [`crate::StdbPlugin::add_table_as_entities`].
+#[derive(EntityEvent, Debug)]
+pub struct EntityRowInserted<T> {
+    /// The entity mirroring the row.
+    pub entity: Entity,
+    /// The row that was inserted.
+    pub row: T,
+}
+
+/// An event that is triggered on the entity mirroring a row, after the row was updated.
+///
+/// Only triggered for tables registered with [`crate::StdbPlugin::add_table_as_entities`].
+#[derive(EntityEvent, Debug)]
+pub struct EntityRowUpdated<T> {
+    /// The entity mirroring the row.
+    pub entity: Entity,
+    /// The old row.
+    pub old: T,
+    /// The new row.
+    pub new: T,
+}
+
+/// An event that is triggered on the entity mirroring a row, right before it is despawned because the row was deleted.
+///
+/// Only triggered for tables registered with [`crate::StdbPlugin::add_table_as_entities`], and not for the entities
+/// despawned when the connection is closed or replaced.
+#[derive(EntityEvent, Debug)]
+pub struct EntityRowDeleted<T> {
+    /// The entity mirroring the row.
+    pub entity: Entity,
+    /// The row that was deleted.
+    pub row: T,
+}
+
+/// The row events already triggered for a table, as a table can be registered several times with different messages.
+#[derive(Resource)]
+struct RowTriggers<T> {
+    triggered: TableMessages,
+    _row: PhantomData<fn() -> T>,
+}
+
+/// Adds the systems triggering the row events of the enabled table messages, once per table and kind of message.
+pub(crate) fn add_row_triggers<T: Clone + Send + Sync + 'static>(
+    world: &mut World,
+    messages: TableMessages,
+) {
+    let triggered = world
+        .get_resource::<RowTriggers<T>>()
+        .map_or_else(TableMessages::default, |triggers| triggers.triggered);
+    world.insert_resource(RowTriggers::<T> {
+        triggered: TableMessages {
+            insert: triggered.insert || messages.insert,
+            update: triggered.update || messages.update,
+            delete: triggered.delete || messages.delete,
+            triggers: true,
+        },
+        _row: PhantomData,
+    });
+
+    let mut schedules = world.resource_mut::<Schedules>();
+    if messages.insert && !triggered.insert {
+        schedules.add_systems(
+            PreUpdate,
+            trigger_inserted::<T>.after(channel_to_message::<InsertMessage<T>>),
+        );
+    }
+    if messages.update && !triggered.update {
+        schedules.add_systems(
+            PreUpdate,
+            trigger_updated::<T>.after(channel_to_message::<UpdateMessage<T>>),
+        );
+    }
+    if messages.delete && !triggered.delete {
+        schedules.add_systems(
+            PreUpdate,
+            trigger_deleted::<T>.after(channel_to_message::<DeleteMessage<T>>),
+        );
+    }
+}
+
+fn trigger_inserted<T: Clone + Send + Sync + 'static>(
+    mut commands: Commands,
+    mut messages: ReadInsertMessage<T>,
+) {
+    for message in messages.read() {
+        commands.trigger(RowInserted {
+            row: message.row.clone(),
+            event: message.event.clone(),
+        });
+    }
+}
+
+fn trigger_updated<T: Clone + Send + Sync + 'static>(
+    mut commands: Commands,
+    mut messages: ReadUpdateMessage<T>,
+) {
+    for message in messages.read() {
+        commands.trigger(RowUpdated {
+            old: message.old.clone(),
+            new: message.new.clone(),
+            event: message.event.clone(),
+        });
+    }
+}
+
+fn trigger_deleted<T: Clone + Send + Sync + 'static>(
+    mut commands: Commands,
+    mut messages: ReadDeleteMessage<T>,
+) {
+    for message in messages.read() {
+        commands.trigger(RowDeleted {
+            row: message.row.clone(),
+            event: message.event.clone(),
+        });
+    }
+}
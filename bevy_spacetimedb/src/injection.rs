@@ -0,0 +1,273 @@
+//! Message injection, to test the systems reading the messages of [`crate::StdbPlugin`] without a SpacetimeDB host.
+//!
+//! Add the plugin with [`crate::StdbPlugin::with_delayed_connect`] so no connection is opened, then use
+//! [`StdbMessageInjectionExt`] to inject rows, reducer results and connection changes into the channels a live
+//! connection sends them through. They are read as messages on the next [`App::update`]:
+//!
+//! ```ignore
+//! let mut app = App::new();
+//! app.add_plugins((MinimalPlugins, StatesPlugin)).add_plugins(
+//!     StdbPlugin::<DbConnection, RemoteModule>::default()
+//!         .with_uri("http://localhost:3000")
+//!         .with_module_name("test")
+//!         .with_run_fn(DbConnection::run_threaded)
+//!         .with_delayed_connect(true)
+//!         .add_table(RemoteTables::player),
+//! );
+//!
+//! app.inject_connected(Identity::ZERO, "token")
+//!     .inject_insert(Player { id: 1, name: "Alice".to_string() });
+//! app.update();
+//! ```
+//!
+//! Only the messages are injected, there is no connection behind them: no [`crate::StdbConnection`] resource is
+//! inserted and reducer calls made with [`crate::StdbCommands`] fail. Rows skip the table callbacks, so
+//! filters, projections and transactions are not applied: inject the rows as they would be sent, and their
+//! [`TransactionMessage`] with [`StdbMessageInjectionExt::inject_transaction`].
+//!
+//! The systems reading the messages run as usual, so the rows still reach the entities of
+//! [`crate::StdbPlugin::add_table_as_entities`] and the row triggers.
+
+use std::{borrow::Cow, marker::PhantomData};
+
+use bevy::{
+    app::App,
+    ecs::{resource::Resource, world::World},
+};
+use spacetimedb_sdk::{Error, Identity};
+
+use crate::{
+    ProcedureResultMessage, ReducerResultMessage, StdbConnectedMessage, StdbConnectionErrorMessage,
+    StdbDisconnectedMessage, StdbEventInfo, SubscriptionAppliedMessage, SubscriptionErrorMessage,
+    TransactionMessage, plugin::StdbMessageSenders, tables::TableSenders,
+};
+
+/// Injects the messages a SpacetimeDB connection would send to the [`crate::StdbPlugin`] of an app or world.
+///
+/// Panics if the plugin was not added, or if the table, reducer or procedure was not registered on it.
+pub trait StdbMessageInjectionExt {
+    /// Sends the messages of a row insertion, as the `on_insert` callback of its table would for a kept row.
+    fn inject_insert<T: Send + Sync + Clone + 'static>(&mut self, row: T) -> &mut Self;
+
+    /// Sends the messages of a row update, as the `on_update` callback of its table would for kept rows.
+    fn inject_update<T: Send + Sync + Clone + 'static>(&mut self, old: T, new: T) -> &mut Self;
+
+    /// Sends the messages of a row deletion, as the `on_delete` callback of its table would for a kept row.
+    fn inject_delete<T: Send + Sync + Clone + 'static>(&mut self, row: T) -> &mut Self;
+
+    /// Sets the event carried by the rows injected afterwards, [`StdbEventInfo::UnknownTransaction`] by default.
+    fn set_injected_row_event(&mut self, event: StdbEventInfo) -> &mut Self;
+
+    /// Sends the result of a reducer registered with [`crate::StdbPlugin::add_reducer`].
+    fn inject_reducer_result<E: Send + Sync + 'static>(&mut self, result: E) -> &mut Self;
+
+    /// Sends the result of a procedure registered with [`crate::StdbPlugin::add_procedure`].
+    fn inject_procedure_result<E: Send + Sync + 'static>(&mut self, result: E) -> &mut Self;
+
+    /// Sends a [`StdbConnectedMessage`].
+    fn inject_connected(
+        &mut self,
+        identity: Identity,
+        access_token: impl Into<String>,
+    ) -> &mut Self;
+
+    /// Sends a [`StdbDisconnectedMessage`].
+    fn inject_disconnected(&mut self, err: Option<Error>) -> &mut Self;
+
+    /// Sends a [`StdbConnectionErrorMessage`].
+    fn inject_connection_error(&mut self, err: Error) -> &mut Self;
+
+    /// Sends a [`SubscriptionAppliedMessage`] for the query.
+    fn inject_subscription_applied(&mut self, query: impl Into<String>) -> &mut Self;
+
+    /// Sends a [`SubscriptionErrorMessage`] for the query.
+    fn inject_subscription_error(&mut self, query: impl Into<String>, err: Error) -> &mut Self;
+
+    /// Sends a [`TransactionMessage`], the plugin must be added with [`crate::StdbPlugin::with_transactions`].
+    fn inject_transaction<R: Send + Sync + 'static>(
+        &mut self,
+        transaction: TransactionMessage<R>,
+    ) -> &mut Self;
+}
+
+impl StdbMessageInjectionExt for World {
+    fn inject_insert<T: Send + Sync + Clone + 'static>(&mut self, row: T) -> &mut Self {
+        table_senders::<T>(self).insert(&row_event(self), Cow::Owned(row));
+        self
+    }
+
+    fn inject_update<T: Send + Sync + Clone + 'static>(&mut self, old: T, new: T) -> &mut Self {
+        table_senders::<T>(self).update(&row_event(self), Cow::Owned(old), Cow::Owned(new));
+        self
+    }
+
+    fn inject_delete<T: Send + Sync + Clone + 'static>(&mut self, row: T) -> &mut Self {
+        table_senders::<T>(self).delete(&row_event(self), Cow::Owned(row));
+        self
+    }
+
+    fn set_injected_row_event(&mut self, event: StdbEventInfo) -> &mut Self {
+        self.insert_resource(RowEvent(event));
+        self
+    }
+
+    fn inject_reducer_result<E: Send + Sync + 'static>(&mut self, result: E) -> &mut Self {
+        let _ = StdbMessageSenders::sender::<ReducerResultMessage<E>>(self)
+            .send(ReducerResultMessage::new(result));
+        self
+    }
+
+    fn inject_procedure_result<E: Send + Sync + 'static>(&mut self, result: E) -> &mut Self {
+        let _ = StdbMessageSenders::sender::<ProcedureResultMessage<E>>(self)
+            .send(ProcedureResultMessage::new(result));
+        self
+    }
+
+    fn inject_connected(
+        &mut self,
+        identity: Identity,
+        access_token: impl Into<String>,
+    ) -> &mut Self {
+        let _ =
+            StdbMessageSenders::sender::<StdbConnectedMessage>(self).send(StdbConnectedMessage {
+                identity,
+                access_token: access_token.into(),
+                label: PhantomData,
+            });
+        self
+    }
+
+    fn inject_disconnected(&mut self, err: Option<Error>) -> &mut Self {
+        let _ = StdbMessageSenders::sender::<StdbDisconnectedMessage>(self).send(
+            StdbDisconnectedMessage {
+                err,
+                label: PhantomData,
+            },
+        );
+        self
+    }
+
+    fn inject_connection_error(&mut self, err: Error) -> &mut Self {
+        let _ = StdbMessageSenders::sender::<StdbConnectionErrorMessage>(self).send(
+            StdbConnectionErrorMessage {
+                err,
+                label: PhantomData,
+            },
+        );
+        self
+    }
+
+    fn inject_subscription_applied(&mut self, query: impl Into<String>) -> &mut Self {
+        let _ = StdbMessageSenders::sender::<SubscriptionAppliedMessage>(self).send(
+            SubscriptionAppliedMessage {
+                query: query.into(),
+                label: PhantomData,
+            },
+        );
+        self
+    }
+
+    fn inject_subscription_error(&mut self, query: impl Into<String>, err: Error) -> &mut Self {
+        let _ = StdbMessageSenders::sender::<SubscriptionErrorMessage>(self).send(
+            SubscriptionErrorMessage {
+                query: query.into(),
+                err,
+                label: PhantomData,
+            },
+        );
+        self
+    }
+
+    fn inject_transaction<R: Send + Sync + 'static>(
+        &mut self,
+        transaction: TransactionMessage<R>,
+    ) -> &mut Self {
+        let _ = StdbMessageSenders::sender::<TransactionMessage<R>>(self).send(transaction);
+        self
+    }
+}
+
+impl StdbMessageInjectionExt for App {
+    fn inject_insert<T: Send + Sync + Clone + 'static>(&mut self, row: T) -> &mut Self {
+        self.world_mut().inject_insert(row);
+        self
+    }
+
+    fn inject_update<T: Send + Sync + Clone + 'static>(&mut self, old: T, new: T) -> &mut Self {
+        self.world_mut().inject_update(old, new);
+        self
+    }
+
+    fn inject_delete<T: Send + Sync + Clone + 'static>(&mut self, row: T) -> &mut Self {
+        self.world_mut().inject_delete(row);
+        self
+    }
+
+    fn set_injected_row_event(&mut self, event: StdbEventInfo) -> &mut Self {
+        self.world_mut().set_injected_row_event(event);
+        self
+    }
+
+    fn inject_reducer_result<E: Send + Sync + 'static>(&mut self, result: E) -> &mut Self {
+        self.world_mut().inject_reducer_result(result);
+        self
+    }
+
+    fn inject_procedure_result<E: Send + Sync + 'static>(&mut self, result: E) -> &mut Self {
+        self.world_mut().inject_procedure_result(result);
+        self
+    }
+
+    fn inject_connected(
+        &mut self,
+        identity: Identity,
+        access_token: impl Into<String>,
+    ) -> &mut Self {
+        self.world_mut().inject_connected(identity, access_token);
+        self
+    }
+
+    fn inject_disconnected(&mut self, err: Option<Error>) -> &mut Self {
+        self.world_mut().inject_disconnected(err);
+        self
+    }
+
+    fn inject_connection_error(&mut self, err: Error) -> &mut Self {
+        self.world_mut().inject_connection_error(err);
+        self
+    }
+
+    fn inject_subscription_applied(&mut self, query: impl Into<String>) -> &mut Self {
+        self.world_mut().inject_subscription_applied(query);
+        self
+    }
+
+    fn inject_subscription_error(&mut self, query: impl Into<String>, err: Error) -> &mut Self {
+        self.world_mut().inject_subscription_error(query, err);
+        self
+    }
+
+    fn inject_transaction<R: Send + Sync + 'static>(
+        &mut self,
+        transaction: TransactionMessage<R>,
+    ) -> &mut Self {
+        self.world_mut().inject_transaction(transaction);
+        self
+    }
+}
+
+/// The event carried by the rows injected with [`StdbMessageInjectionExt`], set with [`StdbMessageInjectionExt::set_injected_row_event`].
+#[derive(Resource)]
+struct RowEvent(StdbEventInfo);
+
+fn row_event(world: &World) -> StdbEventInfo {
+    world
+        .get_resource::<RowEvent>()
+        .map_or(StdbEventInfo::UnknownTransaction, |event| event.0.clone())
+}
+
+fn table_senders<T: Send + Sync + Clone + 'static>(world: &World) -> &TableSenders<T> {
+    world
+        .get_resource::<TableSenders<T>>()
+        .expect("Table not registered, add it with StdbPlugin::add_table()")
+}
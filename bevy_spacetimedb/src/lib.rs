@@ -4,20 +4,50 @@
 
 mod aliases;
 mod channel_receiver;
+mod commands;
+mod entities;
+mod events;
+pub mod injection;
 mod messages;
 mod plugin;
+mod procedures;
+mod reconnect;
+mod reducer_calls;
 mod reducers;
+mod state;
 mod stdb_connection;
+mod subscriptions;
 mod tables;
-mod procedures;
+mod token_store;
+mod transactions;
+
+#[cfg(test)]
+#[path = "../tests/common/mod.rs"]
+mod test_bindings;
 
 pub use aliases::*;
 #[cfg(feature = "macros")]
 pub use bevy_spacetimedb_macros::*;
 
 pub use channel_receiver::AddMessageChannelAppExtensions;
+pub use commands::StdbCommandsExt;
+pub use entities::TableEntities;
+pub use events::*;
 pub use messages::*;
-pub use plugin::{StdbPlugin, StdbPluginConfig, connect_with_token};
+pub use plugin::{
+    StdbPlugin, StdbPluginConfig, connect_labeled, connect_with_request, connect_with_token,
+    disconnect, disconnect_labeled,
+};
+pub use procedures::RegisterableProcedureMessage;
+pub use reconnect::ReconnectPolicy;
+pub use reducer_calls::{ReducerCallFinished, ReducerCallHandle, ReducerCallStatus, StdbCommands};
 pub use reducers::RegisterableReducerMessage;
+pub use state::StdbConnectionState;
 pub use stdb_connection::*;
-pub use tables::{TableMessages, TableMessagesWithoutPrimaryKey};
+pub use subscriptions::{StdbSubscription, StdbSubscriptionState, StdbSubscriptions};
+pub use tables::{
+    BorrowingFn, TableAccessor, TableMessages, TableMessagesWithoutPrimaryKey,
+    TableWithPrimaryKeyAccessor,
+};
+pub use token_store::{FileTokenStore, TokenStore};
+pub use transactions::TransactionMessage;
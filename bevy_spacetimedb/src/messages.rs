@@ -1,27 +1,289 @@
+use std::{fmt, marker::PhantomData, time::Duration};
+
 use bevy::prelude::Message;
-use spacetimedb_sdk::{Error, Identity};
+use spacetimedb_sdk::{
+    __codegen::{Reducer, SpacetimeModule},
+    Compression, ConnectionId, Error, Event, Identity, Timestamp,
+};
+
+/// A message requesting [`crate::StdbPlugin`] to open a new connection, replacing the current one.
+///
+/// Unset parameters default to the ones set on the plugin, so the URI and module name can be picked at runtime
+/// when the plugin is added with [`crate::StdbPlugin::with_delayed_connect`]. The connection can also be opened
+/// right away from an exclusive system with [`crate::connect_with_request`].
+///
+/// A request left without a URI or module name, or with an invalid URI, fails with a
+/// [`StdbConnectionErrorMessage`] and leaves the current connection open.
+///
+/// The label `L` selects the plugin to connect, see [`crate::StdbPlugin`].
+///
+/// ```ignore
+/// fn join_region(mut requests: MessageWriter<StdbConnectRequest>) {
+///     requests.write(
+///         StdbConnectRequest::new()
+///             .with_uri("https://eu.example.com")
+///             .with_module_name("lobby-eu")
+///             .with_token(token),
+///     );
+/// }
+/// ```
+#[derive(Message)]
+pub struct StdbConnectRequest<L = ()> {
+    /// The URI of the SpacetimeDB host, defaults to [`crate::StdbPlugin::with_uri`].
+    pub uri: Option<String>,
+    /// The name or identity of the remote module, defaults to [`crate::StdbPlugin::with_module_name`].
+    pub module_name: Option<String>,
+    /// The token to authenticate with, the host generates a new anonymous `Identity` if `None`.
+    pub token: Option<String>,
+    /// The compression to use, defaults to [`crate::StdbPlugin::with_compression`].
+    pub compression: Option<Compression>,
+    /// Whether to use the light mode, defaults to [`crate::StdbPlugin::with_light_mode`].
+    pub light_mode: Option<bool>,
+    /// The label of the plugin, see [`crate::StdbPlugin`].
+    pub label: PhantomData<fn() -> L>,
+}
+
+// Implemented manually, so the label doesn't need to implement the traits
+impl<L> Default for StdbConnectRequest<L> {
+    fn default() -> Self {
+        Self {
+            uri: None,
+            module_name: None,
+            token: None,
+            compression: None,
+            light_mode: None,
+            label: PhantomData,
+        }
+    }
+}
+
+impl StdbConnectRequest {
+    /// Creates a request for the plugin with the default label, with all parameters unset.
+    ///
+    /// Use `StdbConnectRequest::<L>::default()` for the plugin with the label `L`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<L> Clone for StdbConnectRequest<L> {
+    fn clone(&self) -> Self {
+        Self {
+            uri: self.uri.clone(),
+            module_name: self.module_name.clone(),
+            token: self.token.clone(),
+            compression: self.compression,
+            light_mode: self.light_mode,
+            label: PhantomData,
+        }
+    }
+}
+
+impl<L> fmt::Debug for StdbConnectRequest<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StdbConnectRequest")
+            .field("uri", &self.uri)
+            .field("module_name", &self.module_name)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .field("compression", &self.compression)
+            .field("light_mode", &self.light_mode)
+            .finish()
+    }
+}
+
+/// The message-driven equivalent of [`crate::StdbCommandsExt::stdb_connect`], see [`StdbConnectRequest`].
+///
+/// ```ignore
+/// connect.write(StdbConnectMessage::new().with_token(token));
+/// ```
+pub type StdbConnectMessage<L = ()> = StdbConnectRequest<L>;
+
+impl<L> StdbConnectRequest<L> {
+    /// Set the URI of the SpacetimeDB host.
+    pub fn with_uri(mut self, uri: impl Into<String>) -> Self {
+        self.uri = Some(uri.into());
+        self
+    }
+
+    /// Set the name or identity of the remote module.
+    pub fn with_module_name(mut self, name: impl Into<String>) -> Self {
+        self.module_name = Some(name.into());
+        self
+    }
+
+    /// Set the token to authenticate with.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Set the compression to use.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Set whether to use the light mode.
+    pub fn with_light_mode(mut self, light_mode: bool) -> Self {
+        self.light_mode = Some(light_mode);
+        self
+    }
+}
 
 /// A message that is emitted when a connection to SpacetimeDB is established.
+///
+/// Like the other lifecycle messages, it is emitted for the connection of the plugin with the label `L`.
 #[derive(Message)]
-pub struct StdbConnectedMessage {
+pub struct StdbConnectedMessage<L = ()> {
     /// The `Identity`` of the successful connection.
     pub identity: Identity,
     /// The private access token which can be used to later re-authenticate as the same `Identity`.
     pub access_token: String,
+    /// The label of the plugin, see [`crate::StdbPlugin`].
+    pub label: PhantomData<fn() -> L>,
 }
 
 /// A message that is emitted when a connection to SpacetimeDB is lost.
 #[derive(Message)]
-pub struct StdbDisconnectedMessage {
+pub struct StdbDisconnectedMessage<L = ()> {
     /// The error that caused the disconnection, if any.
     pub err: Option<Error>,
+    /// The label of the plugin, see [`crate::StdbPlugin`].
+    pub label: PhantomData<fn() -> L>,
 }
 
 /// A message that is emitted when a connection to SpacetimeDB encounters an error.
+///
+/// This includes failing to open the connection in the first place,
+/// in which case no [`crate::StdbConnection`] resource is inserted.
+#[derive(Message)]
+pub struct StdbConnectionErrorMessage<L = ()> {
+    /// The error that occurred.
+    pub err: Error,
+    /// The label of the plugin, see [`crate::StdbPlugin`].
+    pub label: PhantomData<fn() -> L>,
+}
+
+/// A message that is emitted when a reconnection attempt has been scheduled after the connection was lost.
+#[derive(Message)]
+pub struct StdbReconnectingMessage<L = ()> {
+    /// The number of the scheduled attempt, starting at 1.
+    pub attempt: u32,
+    /// The delay before the attempt is made.
+    pub delay: Duration,
+    /// The label of the plugin, see [`crate::StdbPlugin`].
+    pub label: PhantomData<fn() -> L>,
+}
+
+impl<L> fmt::Debug for StdbReconnectingMessage<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StdbReconnectingMessage")
+            .field("attempt", &self.attempt)
+            .field("delay", &self.delay)
+            .finish()
+    }
+}
+
+/// A message that is emitted when the connection has been re-established after being lost.
+#[derive(Message)]
+pub struct StdbReconnectedMessage<L = ()> {
+    /// The number of attempts it took to reconnect.
+    pub attempts: u32,
+    /// The label of the plugin, see [`crate::StdbPlugin`].
+    pub label: PhantomData<fn() -> L>,
+}
+
+impl<L> fmt::Debug for StdbReconnectedMessage<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StdbReconnectedMessage")
+            .field("attempts", &self.attempts)
+            .finish()
+    }
+}
+
+/// A message that is emitted when a query of [`crate::StdbSubscriptions`] has been applied,
+/// its rows are then in the client cache.
+#[derive(Message)]
+pub struct SubscriptionAppliedMessage<L = ()> {
+    /// The query that was subscribed to.
+    pub query: String,
+    /// The label of the plugin, see [`crate::StdbPlugin`].
+    pub label: PhantomData<fn() -> L>,
+}
+
+impl<L> fmt::Debug for SubscriptionAppliedMessage<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubscriptionAppliedMessage")
+            .field("query", &self.query)
+            .finish()
+    }
+}
+
+/// A message that is emitted when a query of [`crate::StdbSubscriptions`] fails,
+/// either when it is subscribed to or later on. It is not subscribed to again until the next connection.
 #[derive(Message)]
-pub struct StdbConnectionErrorMessage {
+pub struct SubscriptionErrorMessage<L = ()> {
+    /// The query that failed.
+    pub query: String,
     /// The error that occurred.
     pub err: Error,
+    /// The label of the plugin, see [`crate::StdbPlugin`].
+    pub label: PhantomData<fn() -> L>,
+}
+
+/// The event that caused a row change, carried by the row messages.
+///
+/// ```ignore
+/// fn on_player_inserted(mut messages: ReadInsertMessage<Player>, conn: Res<StdbConnection<DbConnection>>) {
+///     for message in messages.read() {
+///         if let StdbEventInfo::Reducer { caller_connection_id, .. } = message.event {
+///             let ours = caller_connection_id == Some(conn.connection_id());
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StdbEventInfo {
+    /// The rows were changed by a reducer.
+    Reducer {
+        /// The name of the reducer.
+        reducer: &'static str,
+        /// The `Identity` of the caller of the reducer.
+        caller_identity: Identity,
+        /// The `ConnectionId` of the caller of the reducer, `None` for scheduled reducers.
+        caller_connection_id: Option<ConnectionId>,
+        /// The time at which the reducer was invoked.
+        timestamp: Timestamp,
+    },
+    /// The rows were inserted because a subscription was applied.
+    SubscribeApplied,
+    /// The rows were deleted because a subscription was removed.
+    UnsubscribeApplied,
+    /// The rows were deleted because a subscription failed.
+    SubscribeError,
+    /// The rows were deleted because the connection was lost.
+    Disconnected,
+    /// The rows were changed by a transaction not associated with a known reducer, such as an SQL query,
+    /// or by a reducer called by another client when the light mode is used.
+    UnknownTransaction,
+}
+
+impl StdbEventInfo {
+    pub(crate) fn new<M: SpacetimeModule>(event: &Event<M::Reducer>) -> Self {
+        match event {
+            Event::Reducer(event) => Self::Reducer {
+                reducer: event.reducer.reducer_name(),
+                caller_identity: event.caller_identity,
+                caller_connection_id: event.caller_connection_id,
+                timestamp: event.timestamp,
+            },
+            Event::SubscribeApplied => Self::SubscribeApplied,
+            Event::UnsubscribeApplied => Self::UnsubscribeApplied,
+            Event::SubscribeError(_) => Self::SubscribeError,
+            Event::Disconnected => Self::Disconnected,
+            _ => Self::UnknownTransaction,
+        }
+    }
 }
 
 /// A message that is emitted when a row is inserted into a table.
@@ -29,6 +291,16 @@ pub struct StdbConnectionErrorMessage {
 pub struct InsertMessage<T> {
     /// The row that was inserted.
     pub row: T,
+    /// The event that inserted the row.
+    pub event: StdbEventInfo,
+}
+
+impl<T> InsertMessage<T> {
+    /// Whether the row was already in the table and is inserted because a subscription was applied,
+    /// rather than being a new row.
+    pub fn is_initial(&self) -> bool {
+        self.event == StdbEventInfo::SubscribeApplied
+    }
 }
 
 /// A message that is emitted when a row is deleted from a table.
@@ -36,6 +308,8 @@ pub struct InsertMessage<T> {
 pub struct DeleteMessage<T> {
     /// The row that was deleted.
     pub row: T,
+    /// The event that deleted the row.
+    pub event: StdbEventInfo,
 }
 
 /// A message that is emitted when a row is updated in a table.
@@ -45,6 +319,8 @@ pub struct UpdateMessage<T> {
     pub old: T,
     /// The new row.
     pub new: T,
+    /// The event that updated the row.
+    pub event: StdbEventInfo,
 }
 
 /// A message that is emitted when a row is inserted or updated in a table.
@@ -54,6 +330,16 @@ pub struct InsertUpdateMessage<T> {
     pub old: Option<T>,
     /// The new value of the row or the inserted value.
     pub new: T,
+    /// The event that inserted or updated the row.
+    pub event: StdbEventInfo,
+}
+
+impl<T> InsertUpdateMessage<T> {
+    /// Whether the row was already in the table and is inserted because a subscription was applied,
+    /// rather than being a new or updated row.
+    pub fn is_initial(&self) -> bool {
+        self.event == StdbEventInfo::SubscribeApplied
+    }
 }
 
 /// A message that is emitted when a reducer is invoked.
@@ -70,14 +356,15 @@ impl<T> ReducerResultMessage<T> {
     }
 }
 
+/// A message that is emitted when a procedure returns.
 #[derive(Message, Debug)]
 pub struct ProcedureResultMessage<T> {
-    /// The result of the reducer invocation.
+    /// The result of the procedure invocation.
     pub result: T,
 }
 
 impl<T> ProcedureResultMessage<T> {
-    /// Creates a new reducer result message.
+    /// Creates a new procedure result message.
     pub fn new(result: T) -> Self {
         Self { result }
     }
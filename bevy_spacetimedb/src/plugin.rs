@@ -1,144 +1,480 @@
 use crate::{
-    AddMessageChannelAppExtensions, StdbConnectedMessage, StdbConnection,
-    StdbConnectionErrorMessage, StdbDisconnectedMessage,
+    AddMessageChannelAppExtensions, ReconnectPolicy, StdbConnectRequest, StdbConnectedMessage,
+    StdbConnection, StdbConnectionErrorMessage, StdbConnectionState, StdbDisconnectedMessage,
+    StdbReconnectedMessage, StdbReconnectingMessage, SubscriptionAppliedMessage,
+    SubscriptionErrorMessage,
+    channel_receiver::channel_to_message,
+    reconnect::{ReconnectState, schedule_reconnect},
+    reducer_calls::{ReducerCalls, reset_reducer_calls, time_out_reducer_calls},
+    state::update_connection_state,
+    stdb_connection::{DEFAULT_SHUTDOWN_TIMEOUT, UnregisterFn},
+    subscriptions::{
+        ActiveSubscriptions, StdbSubscriptions, SubscriptionStateMessage, subscribe_on_insert,
+        sync_subscriptions, unsubscribe_on_replace, update_subscription_states,
+    },
+    token_store::{StoredToken, TokenStore},
 };
 use bevy::{
-    app::{App, Plugin},
+    app::{App, AppExit, Last, Plugin, PreUpdate},
+    ecs::{message::Message, world::World},
+    log::error,
     platform::collections::HashMap,
-    prelude::Resource,
+    prelude::{
+        AppExtStates, Commands, IntoScheduleConfigs, MessageReader, NextState, Resource, State,
+    },
+    state::app::StatesPlugin,
 };
+use spacetimedb_sdk::{__codegen::http::Uri, Compression, DbConnectionBuilder, DbContext};
 use std::marker::PhantomData;
-use spacetimedb_sdk::{Compression, DbConnectionBuilder, DbContext};
 use std::{
     any::{Any, TypeId},
-    sync::{Arc, Mutex, mpsc::{channel, Sender}},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Sender, channel},
+    },
     thread::JoinHandle,
+    time::Duration,
 };
 
-/// Configuration for delayed SpacetimeDB connection
+/// Configuration used to open (and re-open) the SpacetimeDB connection of the plugin with the label `L`
+///
+/// The module name, URI, compression and light mode are the defaults of every [`StdbConnectRequest`].
 pub struct StdbPluginConfig<
     C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync,
     M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
+    L = (),
 > {
-    pub module_name: String,
-    pub uri: String,
+    pub module_name: Option<String>,
+    pub uri: Option<String>,
     pub run_fn: fn(&C) -> JoinHandle<()>,
     pub compression: Compression,
     pub light_mode: bool,
-    pub send_connected: Sender<StdbConnectedMessage>,
-    pub send_disconnected: Sender<StdbDisconnectedMessage>,
-    pub send_connect_error: Sender<StdbConnectionErrorMessage>,
-    _phantom: PhantomData<(C, M)>,
+    pub shutdown_timeout: Duration,
+    pub token_store: Option<Arc<dyn TokenStore>>,
+    pub clear_token_on_auth_failure: bool,
+    pub send_connected: Sender<StdbConnectedMessage<L>>,
+    pub send_disconnected: Sender<StdbDisconnectedMessage<L>>,
+    pub send_connect_error: Sender<StdbConnectionErrorMessage<L>>,
+    #[allow(clippy::type_complexity)]
+    _phantom: PhantomData<(C, M, fn() -> L)>,
 }
 
 // Manually implement Resource since we can't derive it with PhantomData
 impl<
     C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync + 'static,
     M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C> + 'static,
-> Resource for StdbPluginConfig<C, M> {}
+    L: Send + Sync + 'static,
+> Resource for StdbPluginConfig<C, M, L>
+{
+}
 
-/// Stores plugin data (table/reducer registrations) for delayed connection
-struct DelayedPluginData<
+/// Stores plugin data (table/reducer registrations) to run them on every new connection
+struct StdbPluginData<
     C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync,
     M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
+    L,
 > {
-    message_senders: Arc<Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>>,
     #[allow(clippy::type_complexity)]
-    table_registers: Arc<Mutex<Vec<
-        Box<dyn Fn(&StdbPlugin<C, M>, &mut App, &'static <C as DbContext>::DbView) + Send + Sync>,
-    >>>,
+    table_registers: Arc<
+        Mutex<Vec<Box<dyn Fn(&World, &<C as DbContext>::DbView) -> UnregisterFn<C> + Send + Sync>>>,
+    >,
     #[allow(clippy::type_complexity)]
-    reducer_registers: Arc<Mutex<Vec<Box<dyn Fn(&mut App, &<C as DbContext>::Reducers) + Send + Sync>>>>,
+    reducer_registers:
+        Arc<Mutex<Vec<Box<dyn Fn(&World, &<C as DbContext>::Reducers) + Send + Sync>>>>,
+    _phantom: PhantomData<(M, fn() -> L)>,
+}
+
+/// The senders of all the message channels registered by the plugins, by message type.
+///
+/// The channels are shared by all the plugins of the app, so several connections registering the same table
+/// or reducer send their messages through the same channel.
+#[derive(Resource, Default)]
+pub(crate) struct StdbMessageSenders(
+    pub(crate) Arc<Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>>,
+);
+
+impl StdbMessageSenders {
+    /// Returns the sender of the message channel for `T`, if it was registered.
+    pub(crate) fn get<T: Message>(&self) -> Option<Sender<T>> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(&TypeId::of::<T>())
+            .and_then(|sender| sender.downcast_ref::<Sender<T>>())
+            .cloned()
+    }
+
+    /// Returns the sender of the message channel for `T` registered on the plugins of the world.
+    ///
+    /// Panics if the plugin was not added, or if it did not register the message.
+    pub(crate) fn sender<T: Message>(world: &World) -> Sender<T> {
+        world
+            .get_resource::<StdbMessageSenders>()
+            .expect("StdbMessageSenders not found - did you add the StdbPlugin?")
+            .get::<T>()
+            .expect("Message not registered on the StdbPlugin")
+    }
 }
 
 /// Connect to SpacetimeDB with the given token (for delayed connection mode)
-/// 
+///
 /// Call this from an exclusive system (system with `world: &mut World` parameter)
-/// after OAuth completes to establish the connection with the token.
+/// after OAuth completes to establish the connection with the token, or use
+/// [`crate::StdbCommandsExt::stdb_connect`] from any system.
+///
+/// If the connection cannot be built, a [`StdbConnectionErrorMessage`] is emitted
+/// and no [`StdbConnection`] resource is inserted.
 pub fn connect_with_token<
     C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync,
     M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
 >(
-    world: &mut bevy::prelude::World,
+    world: &mut World,
     token: Option<String>,
 ) {
-    let config = world.remove_resource::<StdbPluginConfig<C, M>>()
-        .expect("StdbPluginConfig not found - did you call with_delayed_connect()?");
-    
-    let plugin_data = world.remove_non_send_resource::<DelayedPluginData<C, M>>()
-        .expect("DelayedPluginData not found");
-    
+    connect_with_request::<C, M>(
+        world,
+        StdbConnectRequest {
+            token,
+            ..Default::default()
+        },
+    );
+}
+
+/// Connect to SpacetimeDB with the given request, replacing the current connection if any.
+///
+/// Call this from an exclusive system (system with `world: &mut World` parameter), use
+/// [`crate::StdbCommandsExt::stdb_connect_with`], or write a [`StdbConnectRequest`] message
+/// to have the plugin connect during the next [`PreUpdate`].
+///
+/// Panics if neither the request nor the plugin set a URI and a module name.
+/// If the connection cannot be built, a [`StdbConnectionErrorMessage`] is emitted
+/// and no [`StdbConnection`] resource is inserted.
+pub fn connect_with_request<
+    C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+    M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
+>(
+    world: &mut World,
+    request: StdbConnectRequest,
+) {
+    connect_labeled::<C, M, ()>(world, request);
+}
+
+/// Same as [`connect_with_request`], for the connection of the plugin with the label `L`.
+///
+/// Use [`crate::StdbCommandsExt::stdb_connect_labeled`] from any system.
+pub fn connect_labeled<
+    C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+    M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
+    L: Send + Sync + 'static,
+>(
+    world: &mut World,
+    request: StdbConnectRequest<L>,
+) {
+    assert!(
+        world.contains_resource::<StdbPluginConfig<C, M, L>>(),
+        "StdbPluginConfig not found - did you add the StdbPlugin?"
+    );
+
+    connect::<C, M, L>(world, request);
+}
+
+/// Closes the connection, without reconnecting even if [`StdbPlugin::with_reconnect`] is used.
+///
+/// The [`StdbConnection`] resource is removed and [`StdbConnectionState`] becomes
+/// [`StdbConnectionState::Disconnected`]. Call this from an exclusive system, or use
+/// [`crate::StdbCommandsExt::stdb_disconnect`].
+pub fn disconnect<C: DbContext + Send + Sync + 'static>(world: &mut World) {
+    disconnect_labeled::<C, ()>(world);
+}
+
+/// Same as [`disconnect`], for the connection of the plugin with the label `L`.
+///
+/// Use [`crate::StdbCommandsExt::stdb_disconnect_labeled`] from any system.
+pub fn disconnect_labeled<C: DbContext + Send + Sync + 'static, L: Send + Sync + 'static>(
+    world: &mut World,
+) {
+    // Dropping the connection disconnects it without reporting it, so no reconnection is scheduled
+    world.remove_resource::<StdbConnection<C, L>>();
+    if let Some(mut state) = world.get_resource_mut::<ReconnectState<L>>() {
+        state.attempt = 0;
+        state.next_attempt_at = None;
+    }
+    reset_reducer_calls::<L>(world);
+    if is_default_label::<L>() {
+        world
+            .resource_mut::<NextState<StdbConnectionState>>()
+            .set(StdbConnectionState::Disconnected);
+    }
+}
+
+/// Whether `L` is the default label, the only one whose connection drives [`StdbConnectionState`].
+fn is_default_label<L: 'static>() -> bool {
+    TypeId::of::<L>() == TypeId::of::<()>()
+}
+
+/// Opens a connection for the last [`StdbConnectRequest`] written since the previous frame.
+fn connect_on_request<
+    C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+    M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
+    L: Send + Sync + 'static,
+>(
+    mut commands: Commands,
+    mut requests: MessageReader<StdbConnectRequest<L>>,
+) {
+    if let Some(request) = requests.read().last().cloned() {
+        commands.queue(move |world: &mut World| connect_labeled::<C, M, L>(world, request));
+    }
+}
+
+/// Builds a new connection, reporting a failure as a [`StdbConnectionErrorMessage`] instead of panicking.
+fn connect<
+    C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+    M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
+    L: Send + Sync + 'static,
+>(
+    world: &mut World,
+    request: StdbConnectRequest<L>,
+) {
+    if let Err(err) = build_connection::<C, M, L>(world, request) {
+        let _ = world
+            .resource::<StdbPluginConfig<C, M, L>>()
+            .send_connect_error
+            .send(StdbConnectionErrorMessage {
+                err,
+                label: PhantomData,
+            });
+    }
+}
+
+/// Resolves the host and module of a request from the request and the [`StdbPluginConfig`].
+fn resolve_host<
+    C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+    M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
+    L: Send + Sync + 'static,
+>(
+    config: &StdbPluginConfig<C, M, L>,
+    request: &StdbConnectRequest<L>,
+) -> Result<(Uri, String), String> {
+    let uri = request.uri.as_ref().or(config.uri.as_ref()).ok_or(
+        "No uri set for the connection. Set it with StdbPlugin::with_uri() or StdbConnectRequest::with_uri()",
+    )?;
+    let uri = uri
+        .parse::<Uri>()
+        .map_err(|err| format!("Invalid uri {uri:?} for the connection: {err}"))?;
+    let module_name = request.module_name.as_ref().or(config.module_name.as_ref()).ok_or(
+        "No module name set for the connection. Set it with StdbPlugin::with_module_name() or StdbConnectRequest::with_module_name()",
+    )?;
+    Ok((uri, module_name.clone()))
+}
+
+/// Builds a new connection from the request and the [`StdbPluginConfig`], registers the tables and reducers on it,
+/// starts it and inserts it as the [`StdbConnection`] resource, replacing any previous one.
+///
+/// A request without a host or module is logged and fails with [`spacetimedb_sdk::Error::Disconnected`],
+/// leaving the current connection open.
+fn build_connection<
+    C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+    M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
+    L: Send + Sync + 'static,
+>(
+    world: &mut World,
+    request: StdbConnectRequest<L>,
+) -> spacetimedb_sdk::Result<()> {
+    let (uri, module_name) = resolve_host(world.resource::<StdbPluginConfig<C, M, L>>(), &request)
+        .map_err(|err| {
+            error!("{err}");
+            spacetimedb_sdk::Error::Disconnected
+        })?;
+
+    // Reconnections reuse the host and module of the last request
+    if let Some(mut state) = world.get_resource_mut::<ReconnectState<L>>() {
+        state.request = request.clone();
+    }
+
+    // Close the previous connection, so it is not left running next to the new one
+    world.remove_resource::<StdbConnection<C, L>>();
+    reset_reducer_calls::<L>(world);
+    // The connection ID is only known once the host has identified the connection
+    let reducer_calls_connection = Arc::clone(&world.resource::<ReducerCalls<L>>().connection_id);
+
+    let config = world.resource::<StdbPluginConfig<C, M, L>>();
     let send_connected = config.send_connected.clone();
     let send_disconnected = config.send_disconnected.clone();
     let send_connect_error = config.send_connect_error.clone();
-    
+    let run_fn = config.run_fn;
+    let shutdown_timeout = config.shutdown_timeout;
+
+    // Without a token, authenticate with the one stored for the module
+    let stored_token = config.token_store.clone().map(|store| StoredToken {
+        store,
+        module_name: module_name.clone(),
+        clear_on_auth_failure: config.clear_token_on_auth_failure,
+    });
+    let token = request
+        .token
+        .or_else(|| stored_token.as_ref().and_then(StoredToken::load));
+    let (stored_token_connect_error, stored_token_connect) =
+        (stored_token.clone(), stored_token.clone());
+
+    // Set once the connection is dropped, so it no longer reports its lifecycle
+    let dropped = Arc::new(AtomicBool::new(false));
+    let (dropped_connect_error, dropped_disconnect, dropped_connect) = (
+        Arc::clone(&dropped),
+        Arc::clone(&dropped),
+        Arc::clone(&dropped),
+    );
+
     let conn = DbConnectionBuilder::<M>::new()
-        .with_module_name(config.module_name)
-        .with_uri(config.uri)
+        .with_module_name(module_name)
+        .with_uri(uri)
         .with_token(token)
-        .with_compression(config.compression)
-        .with_light_mode(config.light_mode)
+        .with_compression(request.compression.unwrap_or(config.compression))
+        .with_light_mode(request.light_mode.unwrap_or(config.light_mode))
         .on_connect_error(move |_ctx, err| {
+            if dropped_connect_error.load(Ordering::Relaxed) {
+                return;
+            }
+            if let Some(stored_token) = &stored_token_connect_error {
+                stored_token.on_connect_error(&err);
+            }
             send_connect_error
-                .send(StdbConnectionErrorMessage { err })
+                .send(StdbConnectionErrorMessage {
+                    err,
+                    label: PhantomData,
+                })
                 .unwrap();
         })
         .on_disconnect(move |_ctx, err| {
+            if dropped_disconnect.load(Ordering::Relaxed) {
+                return;
+            }
             send_disconnected
-                .send(StdbDisconnectedMessage { err })
+                .send(StdbDisconnectedMessage {
+                    err,
+                    label: PhantomData,
+                })
                 .unwrap();
         })
-        .on_connect(move |_ctx, id, token| {
+        .on_connect(move |ctx, id, token| {
+            if dropped_connect.load(Ordering::Relaxed) {
+                return;
+            }
+            *reducer_calls_connection.lock().unwrap() = ctx.try_connection_id();
+            if let Some(stored_token) = &stored_token_connect {
+                stored_token.save(token);
+            }
             send_connected
                 .send(StdbConnectedMessage {
                     identity: id,
                     access_token: token.to_string(),
+                    label: PhantomData,
                 })
                 .unwrap();
         })
         .build()
-        .expect("Failed to build delayed connection");
-
-    let conn = Box::<C>::leak(Box::new(conn));
-
-    // NOW register tables and reducers with the actual connection!
-    // Create a temporary plugin with the stored message senders
-    let temp_plugin = StdbPlugin::<C, M> {
-        module_name: None,
-        uri: None,
-        token: None,
-        run_fn: None,
-        compression: None,
-        light_mode: false,
-        delayed_connect: false,
-        message_senders: Arc::clone(&plugin_data.message_senders),
-        table_registers: Arc::new(Mutex::new(Vec::new())),
-        reducer_registers: Arc::new(Mutex::new(Vec::new())),
-        procedure_registers: Arc::new(Mutex::new(Vec::new())),
-    };
-    
-    // Register tables with the real connection
-    let table_regs = plugin_data.table_registers.lock().unwrap();
-    for table_register in table_regs.iter() {
-        table_register(&temp_plugin, unsafe { &mut *(world as *mut _ as *mut App) }, conn.db());
+        .inspect_err(|err| {
+            if let Some(stored_token) = &stored_token {
+                stored_token.on_connect_error(err);
+            }
+        })?;
+
+    let plugin_data = world
+        .get_non_send_resource::<StdbPluginData<C, M, L>>()
+        .expect("StdbPluginData not found");
+    let table_registers = Arc::clone(&plugin_data.table_registers);
+    let reducer_registers = Arc::clone(&plugin_data.reducer_registers);
+    let message_senders = Arc::clone(&world.resource::<StdbMessageSenders>().0);
+
+    // Attach the table and reducer callbacks to the actual connection,
+    // forwarding them to the messages registered when the plugin was built
+    let connection = StdbConnection::<C, L>::new(conn).with_message_senders(message_senders);
+    for table_register in table_registers.lock().unwrap().iter() {
+        connection.on_drop(table_register(world, connection.db()));
+    }
+    for reducer_register in reducer_registers.lock().unwrap().iter() {
+        reducer_register(world, connection.reducers());
     }
-    drop(table_regs);
-    
-    // Register reducers
-    let reducer_regs = plugin_data.reducer_registers.lock().unwrap();
-    for reducer_register in reducer_regs.iter() {
-        reducer_register(unsafe { &mut *(world as *mut _ as *mut App) }, conn.reducers());
+    connection.on_drop(Box::new(move |_db| dropped.store(true, Ordering::Relaxed)));
+
+    let run_thread = run_fn(connection.conn());
+    world.insert_resource(connection.with_run_thread(run_thread, shutdown_timeout));
+
+    // A reconnection stays in the reconnecting state until it succeeds or gives up
+    if is_default_label::<L>()
+        && *world.resource::<State<StdbConnectionState>>().get()
+            != StdbConnectionState::Reconnecting
+    {
+        world
+            .resource_mut::<NextState<StdbConnectionState>>()
+            .set(StdbConnectionState::Connecting);
     }
-    drop(reducer_regs);
 
-    (config.run_fn)(conn);
-    world.insert_resource(StdbConnection::new(conn));
+    Ok(())
+}
+
+/// Makes the scheduled reconnection attempt, if it is due.
+fn reconnect<
+    C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync,
+    M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
+    L: Send + Sync + 'static,
+>(
+    world: &mut World,
+) {
+    let mut state = world.resource_mut::<ReconnectState<L>>();
+    if !state.take_due_attempt() {
+        return;
+    }
+
+    // A failure is reported as a connection error, which schedules the next attempt
+    let request = StdbConnectRequest {
+        token: state.token.clone(),
+        ..state.request.clone()
+    };
+    connect::<C, M, L>(world, request);
+}
+
+/// Closes the connection when the app exits, so the server sees a clean disconnection.
+fn disconnect_on_exit<C: DbContext + Send + Sync + 'static, L: Send + Sync + 'static>(
+    mut commands: Commands,
+    mut exit: MessageReader<AppExit>,
+) {
+    if exit.read().next().is_some() {
+        // Unlike dropping the connection, waits for its run thread to finish
+        commands.queue(|world: &mut World| {
+            if let Some(connection) = world.remove_resource::<StdbConnection<C, L>>() {
+                connection.shutdown();
+            }
+        });
+    }
 }
 
 /// The plugin for connecting SpacetimeDB with your bevy application.
+///
+/// Several plugins can be added to connect to several modules, or to several databases of the same module,
+/// by giving each of them a different label `L`, any type (defaults to `()`).
+/// The [`StdbConnection`] resource, the lifecycle messages and [`StdbConnectRequest`] of a connection
+/// are those with its label, while [`StdbConnectionState`] follows the connection with the default label.
+///
+/// The plugin with the default label registers [`StdbConnectionState`], so [`StatesPlugin`] (part of
+/// `DefaultPlugins`) must be added before it.
+///
+/// ```ignore
+/// struct Matchmaking;
+/// struct Match;
+///
+/// app.add_plugins(StdbPlugin::<lobby::DbConnection, lobby::RemoteModule, Matchmaking>::default())
+///     .add_plugins(StdbPlugin::<game::DbConnection, game::RemoteModule, Match>::default());
+///
+/// fn on_match_connected(mut messages: ReadStdbConnectedMessage<Match>) {}
+/// ```
+///
+/// Row, reducer and procedure messages are not labeled: connections registering the same table
+/// or reducer emit them through the same messages.
 pub struct StdbPlugin<
     C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext,
     M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
+    L = (),
 > {
     module_name: Option<String>,
     uri: Option<String>,
@@ -146,26 +482,35 @@ pub struct StdbPlugin<
     run_fn: Option<fn(&C) -> JoinHandle<()>>,
     compression: Option<Compression>,
     light_mode: bool,
-    delayed_connect: bool,  // NEW: Skip immediate connection
+    delayed_connect: bool, // NEW: Skip immediate connection
+    reconnect: Option<ReconnectPolicy>,
+    reducer_call_timeout: Duration,
+    shutdown_timeout: Duration,
+    token_store: Option<Arc<dyn TokenStore>>,
+    clear_token_on_auth_failure: bool,
+    subscriptions: Vec<String>,
+    label: PhantomData<fn() -> L>,
 
-    // Stores Senders for registered table messages.
-    pub(crate) message_senders: Arc<Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>>,
+    // Attach the callbacks of tables and reducers to every new connection, the senders of their
+    // messages are looked up in the world.
     #[allow(clippy::type_complexity)]
-    pub(crate) table_registers: Arc<Mutex<Vec<
-        Box<dyn Fn(&StdbPlugin<C, M>, &mut App, &'static <C as DbContext>::DbView) + Send + Sync>,
-    >>>,
+    pub(crate) table_registers: Arc<
+        Mutex<Vec<Box<dyn Fn(&World, &<C as DbContext>::DbView) -> UnregisterFn<C> + Send + Sync>>>,
+    >,
     #[allow(clippy::type_complexity)]
     pub(crate) reducer_registers:
-        Arc<Mutex<Vec<Box<dyn Fn(&mut App, &<C as DbContext>::Reducers) + Send + Sync>>>>,
+        Arc<Mutex<Vec<Box<dyn Fn(&World, &<C as DbContext>::Reducers) + Send + Sync>>>>,
+    // Register the messages of tables, reducers and procedures when the plugin is built.
     #[allow(clippy::type_complexity)]
-    pub(crate) procedure_registers:
-        Arc<Mutex<Vec<Box<dyn Fn(&mut App, &<C as DbContext>::Procedures) + Send + Sync>>>>,
+    pub(crate) message_registers:
+        Arc<Mutex<Vec<Box<dyn Fn(&StdbPlugin<C, M, L>, &mut World) + Send + Sync>>>>,
 }
 
 impl<
     C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext,
     M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
-> Default for StdbPlugin<C, M>
+    L,
+> Default for StdbPlugin<C, M, L>
 {
     fn default() -> Self {
         Self {
@@ -175,20 +520,49 @@ impl<
             run_fn: None,
             compression: Some(Compression::default()),
             light_mode: false,
-            delayed_connect: false,  // NEW: Default to immediate connection
+            delayed_connect: false, // NEW: Default to immediate connection
+            reconnect: None,
+            reducer_call_timeout: Duration::from_secs(30),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            token_store: None,
+            clear_token_on_auth_failure: false,
+            subscriptions: Vec::new(),
+            label: PhantomData,
 
-            message_senders: Arc::new(Mutex::default()),
             table_registers: Arc::new(Mutex::new(Vec::default())),
             reducer_registers: Arc::new(Mutex::new(Vec::default())),
-            procedure_registers: Arc::new(Mutex::new(Vec::default())),
+            message_registers: Arc::new(Mutex::new(Vec::default())),
         }
     }
 }
 
+impl<
+    C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext,
+    M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
+    L,
+> StdbPlugin<C, M, L>
+{
+    /// Returns the sender of the message channel for `T`, registering the channel on first use.
+    pub(crate) fn message_sender<T: Message>(&self, world: &mut World) -> Sender<T> {
+        let senders = Arc::clone(&world.get_resource_or_init::<StdbMessageSenders>().0);
+        let mut map = senders.lock().unwrap();
+        map.entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                let (send, recv) = channel::<T>();
+                world.add_message_channel(recv);
+                Box::new(send)
+            })
+            .downcast_ref::<Sender<T>>()
+            .expect("Sender type mismatch")
+            .clone()
+    }
+}
+
 impl<
     C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Send + Sync,
     M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
-> StdbPlugin<C, M>
+    L,
+> StdbPlugin<C, M, L>
 {
     /// The function that the connection will run with. The recommended function is `DbConnection::run_threaded`.
     ///
@@ -251,110 +625,210 @@ impl<
     }
 
     /// Enable delayed connection mode. The connection will not be started
-    /// during plugin build. You must manually call `connect_with_token()` later.
+    /// during plugin build. You must manually call `connect_with_token()` later,
+    /// or write a [`StdbConnectRequest`] message.
     ///
-    /// This is useful for OAuth flows where the token is not available at app startup.
+    /// This is useful for OAuth flows where the token is not available at app startup,
+    /// or when the host and module are picked at runtime: the URI and module name are then optional.
     pub fn with_delayed_connect(mut self, delayed: bool) -> Self {
         self.delayed_connect = delayed;
         self
     }
+
+    /// Automatically reconnect when the connection is lost, following the given policy.
+    ///
+    /// Each new connection re-registers all tables and reducers, replaces the [`StdbConnection`] resource
+    /// and reuses the access token of the previous one. Progress is reported through
+    /// [`StdbReconnectingMessage`] and [`StdbReconnectedMessage`].
+    pub fn with_reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = Some(policy);
+        self
+    }
+
+    /// Sets how long a reducer call made with [`crate::StdbCommands`] waits for its result
+    /// before being marked as timed out. Defaults to 30 seconds.
+    pub fn with_reducer_call_timeout(mut self, timeout: Duration) -> Self {
+        self.reducer_call_timeout = timeout;
+        self
+    }
+
+    /// Sets how long to wait for the connection to close when the app exits, before leaving its thread
+    /// behind. Defaults to 5 seconds. A replaced connection closes in the background without blocking.
+    ///
+    /// Reducer calls made before the app exits are sent before the connection closes.
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Persist the access tokens in the given store, to re-authenticate as the same `Identity` on the next launch.
+    ///
+    /// Connections opened without a token use the one stored for their module, if any,
+    /// and the token received once connected is saved.
+    pub fn with_token_store(mut self, store: impl TokenStore) -> Self {
+        self.token_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Sets whether the stored token of a module is cleared when the host rejects it, so the next connection
+    /// gets a new anonymous `Identity` instead of failing again. Disabled by default.
+    pub fn with_clear_token_on_auth_failure(mut self, clear: bool) -> Self {
+        self.clear_token_on_auth_failure = clear;
+        self
+    }
+
+    /// Subscribe to the given queries every time the connection is established, see [`StdbSubscriptions`].
+    ///
+    /// ```ignore
+    /// plugin.with_subscriptions(["SELECT * FROM player", "SELECT * FROM lobby"])
+    /// ```
+    pub fn with_subscriptions(
+        mut self,
+        queries: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.subscriptions
+            .extend(queries.into_iter().map(Into::into));
+        self
+    }
 }
 
 impl<
-    C: spacetimedb_sdk::__codegen::DbConnection<Module = M> + DbContext + Sync,
+    C: spacetimedb_sdk::__codegen::DbConnection<Module = M>
+        + DbContext<SubscriptionBuilder = spacetimedb_sdk::__codegen::SubscriptionBuilder<M>>
+        + Sync,
     M: spacetimedb_sdk::__codegen::SpacetimeModule<DbConnection = C>,
-> Plugin for StdbPlugin<C, M>
+    L: Send + Sync + 'static,
+> Plugin for StdbPlugin<C, M, L>
 {
     fn build(&self, app: &mut App) {
-        self.uri
-            .clone()
-            .expect("No uri set for StdbPlugin. Set it with the with_uri() function");
-        self.module_name.clone().expect(
-            "No module name set for StdbPlugin. Set it with the with_module_name() function",
-        );
-
-        let (send_connected, recv_connected) = channel::<StdbConnectedMessage>();
-        let (send_disconnected, recv_disconnected) = channel::<StdbDisconnectedMessage>();
-        let (send_connect_error, recv_connect_error) = channel::<StdbConnectionErrorMessage>();
-        app.add_message_channel::<StdbConnectionErrorMessage>(recv_connect_error)
-            .add_message_channel::<StdbConnectedMessage>(recv_connected)
-            .add_message_channel::<StdbDisconnectedMessage>(recv_disconnected);
-
-        // NEW: Check if we should delay the connection
-        if self.delayed_connect {
-            // Store configuration AND table/reducer registrations for later connection
-            app.insert_resource(StdbPluginConfig::<C, M> {
-                module_name: self.module_name.clone().unwrap(),
-                uri: self.uri.clone().unwrap(),
-                run_fn: self.run_fn.expect("No run function specified!"),
-                compression: self.compression.unwrap_or_default(),
-                light_mode: self.light_mode,
-                send_connected,
-                send_disconnected,
-                send_connect_error,
-                _phantom: PhantomData,
-            });
-            
-            // Clone the Arc pointers to share the data with connect_with_token
-            let plugin_for_later = DelayedPluginData::<C, M> {
-                table_registers: Arc::clone(&self.table_registers),
-                reducer_registers: Arc::clone(&self.reducer_registers),
-                message_senders: Arc::clone(&self.message_senders),
-            };
-            app.insert_non_send_resource(plugin_for_later);
-            
-            return; // Skip connection - it will be created later via connect_with_token
+        // A delayed connection can get them from its StdbConnectRequest instead
+        if !self.delayed_connect {
+            self.uri
+                .clone()
+                .expect("No uri set for StdbPlugin. Set it with the with_uri() function");
+            self.module_name.clone().expect(
+                "No module name set for StdbPlugin. Set it with the with_module_name() function",
+            );
         }
 
-        // FIXME App should not crash if intial connection fails.
-        let conn = DbConnectionBuilder::<M>::new()
-            .with_module_name(self.module_name.clone().unwrap())
-            .with_uri(self.uri.clone().unwrap())
-            .with_token(self.token.clone())
-            .with_compression(self.compression.unwrap_or_default())
-            .with_light_mode(self.light_mode)
-            .on_connect_error(move |_ctx, err| {
-                send_connect_error
-                    .send(StdbConnectionErrorMessage { err })
-                    .unwrap();
-            })
-            .on_disconnect(move |_ctx, err| {
-                send_disconnected
-                    .send(StdbDisconnectedMessage { err })
-                    .unwrap();
-            })
-            .on_connect(move |_ctx, id, token| {
-                send_connected
-                    .send(StdbConnectedMessage {
-                        identity: id,
-                        access_token: token.to_string(),
-                    })
-                    .unwrap();
-            })
-            .build()
-            .expect("Failed to build connection");
+        let send_connect_error =
+            self.message_sender::<StdbConnectionErrorMessage<L>>(app.world_mut());
+        let send_connected = self.message_sender::<StdbConnectedMessage<L>>(app.world_mut());
+        let send_disconnected = self.message_sender::<StdbDisconnectedMessage<L>>(app.world_mut());
+
+        app.add_message::<StdbReconnectingMessage<L>>()
+            .add_message::<StdbReconnectedMessage<L>>();
 
-        // A 'static ref is needed for the connection the register tables and reducers
-        // This is fine because only a small and fixed amount of memory will be leaked
-        // Because conn has to live until the end of the program anyways, not using it would not make for any performance improvements.
-        let conn = Box::<C>::leak(Box::new(conn));
+        app.insert_resource(ReducerCalls::<L>::new(self.reducer_call_timeout))
+            .add_systems(PreUpdate, time_out_reducer_calls::<L>);
 
+        // Messages are registered up front, so they can be read before the connection is opened
         {
-            let table_regs = self.table_registers.lock().unwrap();
-            for table_register in table_regs.iter() {
-                table_register(self, app, conn.db());
+            let message_regs = self.message_registers.lock().unwrap();
+            for message_register in message_regs.iter() {
+                message_register(self, app.world_mut());
             }
         }
-        {
-            let reducer_regs = self.reducer_registers.lock().unwrap();
-            for reducer_register in reducer_regs.iter() {
-                reducer_register(app, conn.reducers());
+
+        // Store configuration AND table/reducer registrations to build the connection(s) from
+        app.insert_resource(StdbPluginConfig::<C, M, L> {
+            module_name: self.module_name.clone(),
+            uri: self.uri.clone(),
+            run_fn: self.run_fn.expect("No run function specified!"),
+            compression: self.compression.unwrap_or_default(),
+            light_mode: self.light_mode,
+            shutdown_timeout: self.shutdown_timeout,
+            token_store: self.token_store.clone(),
+            clear_token_on_auth_failure: self.clear_token_on_auth_failure,
+            send_connected,
+            send_disconnected,
+            send_connect_error,
+            _phantom: PhantomData,
+        });
+        app.insert_non_send_resource(StdbPluginData::<C, M, L> {
+            table_registers: Arc::clone(&self.table_registers),
+            reducer_registers: Arc::clone(&self.reducer_registers),
+            _phantom: PhantomData,
+        });
+
+        // Only the connection with the default label drives the connection state
+        let tracks_state = is_default_label::<L>();
+        if tracks_state {
+            assert!(
+                app.is_plugin_added::<StatesPlugin>(),
+                "StdbPlugin needs the StatesPlugin, add it or DefaultPlugins before StdbPlugin"
+            );
+            app.init_state::<StdbConnectionState>();
+        }
+
+        if let Some(policy) = &self.reconnect {
+            app.insert_resource(ReconnectState::<L>::new(policy.clone(), self.token.clone()));
+            // Read the lifecycle messages of this frame, so a lost connection is rescheduled right away
+            let schedule_reconnect = schedule_reconnect::<L>
+                .after(channel_to_message::<StdbConnectedMessage<L>>)
+                .after(channel_to_message::<StdbDisconnectedMessage<L>>)
+                .after(channel_to_message::<StdbConnectionErrorMessage<L>>);
+            if tracks_state {
+                app.add_systems(
+                    PreUpdate,
+                    (
+                        schedule_reconnect,
+                        update_connection_state,
+                        reconnect::<C, M, L>,
+                    )
+                        .chain(),
+                );
+            } else {
+                app.add_systems(
+                    PreUpdate,
+                    (schedule_reconnect, reconnect::<C, M, L>).chain(),
+                );
             }
+        } else if tracks_state {
+            app.add_systems(
+                PreUpdate,
+                update_connection_state
+                    .after(channel_to_message::<StdbConnectedMessage>)
+                    .after(channel_to_message::<StdbDisconnectedMessage>)
+                    .after(channel_to_message::<StdbConnectionErrorMessage>),
+            );
         }
+        app.add_message::<StdbConnectRequest<L>>()
+            .add_systems(PreUpdate, connect_on_request::<C, M, L>)
+            .add_systems(Last, disconnect_on_exit::<C, L>);
 
-        let run_fn = self.run_fn.expect("No run function specified!");
-        run_fn(conn);
+        let send_applied = self.message_sender::<SubscriptionAppliedMessage<L>>(app.world_mut());
+        let send_error = self.message_sender::<SubscriptionErrorMessage<L>>(app.world_mut());
+        let send_entity_state = self.message_sender::<SubscriptionStateMessage<L>>(app.world_mut());
+        app.insert_resource(StdbSubscriptions::<L>::new(self.subscriptions.clone()))
+            .insert_resource(ActiveSubscriptions::<M, L>::new(
+                send_applied,
+                send_error,
+                send_entity_state,
+            ))
+            .add_systems(
+                PreUpdate,
+                (
+                    sync_subscriptions::<C, M, L>
+                        .after(channel_to_message::<StdbConnectedMessage<L>>),
+                    update_subscription_states::<M, L>
+                        .after(channel_to_message::<SubscriptionStateMessage<L>>),
+                ),
+            )
+            .add_observer(subscribe_on_insert::<C, M, L>)
+            .add_observer(unsubscribe_on_replace::<M, L>);
 
-        app.insert_resource(StdbConnection::new(conn));
+        // NEW: Check if we should delay the connection
+        if self.delayed_connect {
+            return; // Skip connection - it will be created later via connect_with_token
+        }
+
+        connect::<C, M, L>(
+            app.world_mut(),
+            StdbConnectRequest {
+                token: self.token.clone(),
+                ..Default::default()
+            },
+        );
     }
 }
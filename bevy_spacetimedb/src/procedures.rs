@@ -1,38 +1,80 @@
-use crate::{AddMessageChannelAppExtensions, ProcedureResultMessage, StdbPlugin};
-use bevy::app::App;
+use crate::{ProcedureResultMessage, StdbConnection, StdbPlugin};
+use bevy::ecs::world::World;
 use spacetimedb_sdk::__codegen as spacetime_codegen;
-use std::sync::mpsc::{Sender, channel};
+use std::{any::TypeId, sync::mpsc::Sender};
 
 /// Trait for making a procedure registerable into the bevy application.
+///
+/// Unlike reducers, procedures have no global callback: their result is only delivered
+/// to the call that invoked them, so the call itself is made through this trait.
 pub trait RegisterableProcedureMessage<
     C: spacetime_codegen::DbConnection<Module = M> + spacetimedb_sdk::DbContext,
     M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
 > where
     Self: Sized,
 {
-    /// The function that should define the stdb callback behaviour, and send a bevy message through sender.
-    fn set_stdb_callback(procedures: &C::Procedures, sender: Sender<ProcedureResultMessage<Self>>);
+    /// The arguments the procedure is called with.
+    type Args;
+
+    /// The function that should call the procedure, and send its result as a bevy message through sender.
+    fn invoke(
+        procedures: &C::Procedures,
+        args: Self::Args,
+        sender: Sender<ProcedureResultMessage<Self>>,
+    );
 }
 
 impl<
     C: spacetime_codegen::DbConnection<Module = M> + spacetimedb_sdk::DbContext,
     M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
-> StdbPlugin<C, M>
+    L: Send + Sync + 'static,
+> StdbPlugin<C, M, L>
 {
     /// Registers a procedure message <E> for the bevy application.
+    ///
+    /// Call the procedure with [`StdbConnection::call_procedure`], its result is emitted as a
+    /// [`ProcedureResultMessage<E>`].
     pub fn add_procedure<E: RegisterableProcedureMessage<C, M> + Send + Sync + 'static>(
-        mut self,
+        self,
     ) -> Self {
         // This callback manages the registration of the message.
-        let register_fn = move |app: &mut App, procedures: &C::Procedures| {
-            let (send, recv) = channel::<ProcedureResultMessage<E>>();
-            app.add_message_channel(recv);
-            E::set_stdb_callback(procedures, send);
+        let register_fn = move |plugin: &Self, world: &mut World| {
+            plugin.message_sender::<ProcedureResultMessage<E>>(world);
         };
 
-        // The register_fn will get called once the connection is built.
-        self.procedure_registers.lock().unwrap().push(Box::new(register_fn));
+        // The register_fn will get called when the plugin is built, as procedures are called per-request
+        // no connection is needed to register them.
+        self.message_registers
+            .lock()
+            .unwrap()
+            .push(Box::new(register_fn));
 
         self
     }
 }
+
+impl<
+    C: spacetime_codegen::DbConnection<Module = M> + spacetimedb_sdk::DbContext,
+    M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
+    L,
+> StdbConnection<C, L>
+{
+    /// Calls the procedure <E> with the given arguments, its result will be emitted as a [`ProcedureResultMessage<E>`].
+    ///
+    /// Panics if the procedure was not registered with [`StdbPlugin::add_procedure`].
+    pub fn call_procedure<E: RegisterableProcedureMessage<C, M> + Send + Sync + 'static>(
+        &self,
+        args: E::Args,
+    ) {
+        let sender = self
+            .message_senders
+            .lock()
+            .unwrap()
+            .get(&TypeId::of::<ProcedureResultMessage<E>>())
+            .and_then(|sender| sender.downcast_ref::<Sender<ProcedureResultMessage<E>>>())
+            .expect("Procedure not registered, add it with StdbPlugin::add_procedure()")
+            .clone();
+
+        E::invoke(self.procedures(), args, sender);
+    }
+}
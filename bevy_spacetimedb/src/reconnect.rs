@@ -0,0 +1,221 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    marker::PhantomData,
+    time::Duration,
+};
+
+use bevy::{
+    platform::time::Instant,
+    prelude::{MessageReader, MessageWriter, ResMut, Resource},
+};
+
+use crate::{
+    StdbConnectRequest, StdbConnectedMessage, StdbConnectionErrorMessage, StdbDisconnectedMessage,
+    StdbReconnectedMessage, StdbReconnectingMessage,
+};
+
+/// Describes how [`crate::StdbPlugin`] should try to re-establish a lost connection.
+///
+/// The delay before attempt `n` (starting at 1) is `initial_delay * multiplier^(n - 1)`,
+/// capped at `max_delay`, then randomly spread by `jitter` using the `jitter_source`.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// The delay before the first reconnection attempt.
+    pub initial_delay: Duration,
+    /// The upper bound of the delay between two attempts, before jitter is applied.
+    pub max_delay: Duration,
+    /// The factor applied to the delay after each failed attempt.
+    pub multiplier: f64,
+    /// The fraction (between `0.0` and `1.0`) by which each delay is randomly increased or decreased.
+    pub jitter: f64,
+    /// The maximum number of consecutive attempts, `None` to retry forever.
+    pub max_attempts: Option<u32>,
+    /// Returns a random value in `0.0..=1.0` used to spread each delay, `0.5` leaves it unchanged.
+    pub jitter_source: fn() -> f32,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.1,
+            max_attempts: None,
+            jitter_source: random_jitter,
+        }
+    }
+}
+
+/// A random value in `0.0..=1.0`, good enough to spread clients reconnecting at the same time.
+fn random_jitter() -> f32 {
+    (RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64) as f32
+}
+
+impl ReconnectPolicy {
+    /// Set the delay before the first reconnection attempt.
+    pub fn with_initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Set the upper bound of the delay between two attempts.
+    pub fn with_max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Set the factor applied to the delay after each failed attempt.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Set the fraction by which each delay is randomly increased or decreased, clamped to `0.0..=1.0`.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the maximum number of consecutive attempts before giving up.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Set the function returning the random value (in `0.0..=1.0`) used to spread each delay.
+    ///
+    /// Use a constant, e.g. `|| 0.5`, to get predictable delays in tests.
+    pub fn with_jitter_source(mut self, source: fn() -> f32) -> Self {
+        self.jitter_source = source;
+        self
+    }
+
+    /// Returns the delay to wait before the given attempt (starting at 1).
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let base = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        let base = base.min(self.max_delay.as_secs_f64());
+
+        let random = f64::from((self.jitter_source)().clamp(0.0, 1.0));
+        let spread = 1.0 + self.jitter * (random * 2.0 - 1.0);
+
+        Duration::try_from_secs_f64(base * spread).unwrap_or(self.max_delay)
+    }
+}
+
+/// Tracks the progress of the reconnection attempts of the connection with the label `L`.
+#[derive(Resource)]
+pub(crate) struct ReconnectState<L = ()> {
+    pub(crate) policy: ReconnectPolicy,
+    /// The number of attempts made since the connection was lost, 0 while connected.
+    pub(crate) attempt: u32,
+    /// When the next attempt is due, if one is scheduled.
+    pub(crate) next_attempt_at: Option<Instant>,
+    /// The last access token received, used to reconnect as the same `Identity`.
+    pub(crate) token: Option<String>,
+    /// The last connection request, used to reconnect to the same host and module.
+    pub(crate) request: StdbConnectRequest<L>,
+}
+
+impl<L> ReconnectState<L> {
+    pub(crate) fn new(policy: ReconnectPolicy, token: Option<String>) -> Self {
+        Self {
+            policy,
+            attempt: 0,
+            next_attempt_at: None,
+            token,
+            request: StdbConnectRequest::<L>::default(),
+        }
+    }
+
+    /// Returns `true` and clears the schedule if an attempt is due.
+    pub(crate) fn take_due_attempt(&mut self) -> bool {
+        match self.next_attempt_at {
+            Some(at) if at <= Instant::now() => {
+                self.next_attempt_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Schedules a reconnection attempt whenever the connection is lost or an attempt fails,
+/// and emits [`StdbReconnectedMessage`] once a connection is established again.
+pub(crate) fn schedule_reconnect<L: Send + Sync + 'static>(
+    mut state: ResMut<ReconnectState<L>>,
+    mut connected: MessageReader<StdbConnectedMessage<L>>,
+    mut disconnected: MessageReader<StdbDisconnectedMessage<L>>,
+    mut connection_errors: MessageReader<StdbConnectionErrorMessage<L>>,
+    mut reconnecting: MessageWriter<StdbReconnectingMessage<L>>,
+    mut reconnected: MessageWriter<StdbReconnectedMessage<L>>,
+) {
+    for message in connected.read() {
+        state.token = Some(message.access_token.clone());
+        if state.attempt > 0 {
+            reconnected.write(StdbReconnectedMessage {
+                attempts: state.attempt,
+                label: PhantomData,
+            });
+        }
+        state.attempt = 0;
+        state.next_attempt_at = None;
+    }
+
+    let lost = disconnected.read().count() > 0;
+    // Connection errors only matter once the connection has been lost at least once,
+    // errors of the initial connection are left to the application.
+    let failed = connection_errors.read().count() > 0 && state.attempt > 0;
+    if !(lost || failed) || state.next_attempt_at.is_some() {
+        return;
+    }
+
+    if state
+        .policy
+        .max_attempts
+        .is_some_and(|max| state.attempt >= max)
+    {
+        return;
+    }
+
+    state.attempt += 1;
+    let delay = state.policy.delay_for_attempt(state.attempt);
+    state.next_attempt_at = Some(Instant::now() + delay);
+    reconnecting.write(StdbReconnectingMessage {
+        attempt: state.attempt,
+        delay,
+        label: PhantomData,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> ReconnectPolicy {
+        ReconnectPolicy::default()
+            .with_initial_delay(Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(10))
+            .with_jitter(0.5)
+    }
+
+    #[test]
+    fn delays_grow_up_to_the_max_delay() {
+        let policy = policy().with_jitter_source(|| 0.5);
+        let delays: Vec<_> = (1..=5)
+            .map(|attempt| policy.delay_for_attempt(attempt).as_secs())
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 8, 10]);
+    }
+
+    #[test]
+    fn delays_are_spread_by_the_jitter_source() {
+        let shortest = policy().with_jitter_source(|| 0.0);
+        let longest = policy().with_jitter_source(|| 1.0);
+        assert_eq!(shortest.delay_for_attempt(2), Duration::from_secs(1));
+        assert_eq!(longest.delay_for_attempt(2), Duration::from_secs(3));
+        assert_eq!(longest.delay_for_attempt(5), Duration::from_secs(15));
+    }
+}
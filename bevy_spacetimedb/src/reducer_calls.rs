@@ -0,0 +1,432 @@
+use std::{
+    any::TypeId,
+    collections::VecDeque,
+    marker::PhantomData,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    ecs::{system::SystemParam, world::World},
+    platform::collections::HashMap,
+    prelude::{Commands, Event, MessageReader, Res, Resource},
+};
+use spacetimedb_sdk::{__codegen as spacetime_codegen, ConnectionId, DbContext, Status};
+
+use crate::{ReducerResultMessage, RegisterableReducerMessage, StdbConnection};
+
+/// The status of a reducer call made with [`StdbCommands::call_reducer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReducerCallStatus {
+    /// The call was sent, its result has not been received yet.
+    Pending,
+    /// The reducer ran and its changes were committed.
+    Committed,
+    /// The reducer failed, the call could not be sent, or the connection was closed or replaced before
+    /// the result was received, with the given error message.
+    Failed(String),
+    /// The reducer was aborted because the module ran out of energy.
+    OutOfEnergy,
+    /// No result was received before the timeout set with [`crate::StdbPlugin::with_reducer_call_timeout`].
+    TimedOut,
+}
+
+impl From<&Status> for ReducerCallStatus {
+    fn from(status: &Status) -> Self {
+        match status {
+            Status::Committed => Self::Committed,
+            Status::Failed(message) => Self::Failed(message.to_string()),
+            Status::OutOfEnergy => Self::OutOfEnergy,
+        }
+    }
+}
+
+/// A handle to a reducer call made with [`StdbCommands::call_reducer`], used to poll its status.
+///
+/// A [`ReducerCallFinished`] event is also triggered once the call is no longer pending.
+#[derive(Debug, Clone)]
+pub struct ReducerCallHandle {
+    id: u64,
+    status: Arc<Mutex<ReducerCallStatus>>,
+}
+
+impl ReducerCallHandle {
+    fn new(id: u64, status: ReducerCallStatus) -> Self {
+        Self {
+            id,
+            status: Arc::new(Mutex::new(status)),
+        }
+    }
+
+    /// A unique identifier of the call.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The current status of the call.
+    pub fn status(&self) -> ReducerCallStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Whether the result of the call has not been received yet.
+    pub fn is_pending(&self) -> bool {
+        *self.status.lock().unwrap() == ReducerCallStatus::Pending
+    }
+
+    fn finish(&self, status: ReducerCallStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+}
+
+/// An event that is triggered when a reducer call made with [`StdbCommands::call_reducer`] is no longer pending.
+#[derive(Event, Debug, Clone)]
+pub struct ReducerCallFinished {
+    /// The handle of the finished call.
+    pub handle: ReducerCallHandle,
+    /// The final status of the call.
+    pub status: ReducerCallStatus,
+}
+
+struct PendingReducerCall {
+    handle: ReducerCallHandle,
+    sent_at: Instant,
+}
+
+/// The reducer calls of the connection with the label `L` waiting for their result, per reducer message type.
+#[derive(Resource)]
+pub struct ReducerCalls<L = ()> {
+    next_id: AtomicU64,
+    timeout: Duration,
+    /// Set from the connection callbacks once the host has identified the connection.
+    pub(crate) connection_id: Arc<Mutex<Option<ConnectionId>>>,
+    pending: Mutex<HashMap<TypeId, VecDeque<PendingReducerCall>>>,
+    label: PhantomData<fn() -> L>,
+}
+
+impl<L> ReducerCalls<L> {
+    pub(crate) fn new(timeout: Duration) -> Self {
+        Self {
+            next_id: AtomicU64::new(0),
+            timeout,
+            connection_id: Arc::default(),
+            pending: Mutex::default(),
+            label: PhantomData,
+        }
+    }
+
+    /// Starts tracking the calls of the reducer message `E`, returns `false` if they already were.
+    pub(crate) fn track<E: 'static>(&self) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.contains_key(&TypeId::of::<E>()) {
+            return false;
+        }
+        pending.insert(TypeId::of::<E>(), VecDeque::new());
+        true
+    }
+
+    /// Makes a call of the reducer message `E` with `call`, pending until its result is received if it was sent.
+    fn send<E: 'static>(
+        &self,
+        call: impl FnOnce() -> spacetimedb_sdk::Result<()>,
+    ) -> ReducerCallHandle {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        // Hold the lock while calling, so concurrent calls are queued in the order they are sent
+        let mut pending = self.pending.lock().unwrap();
+        let queue = pending
+            .get_mut(&TypeId::of::<E>())
+            .expect("Reducer not registered, add it with StdbPlugin::add_reducer()");
+
+        match call() {
+            Ok(()) => {
+                let handle = ReducerCallHandle::new(id, ReducerCallStatus::Pending);
+                queue.push_back(PendingReducerCall {
+                    handle: handle.clone(),
+                    sent_at: Instant::now(),
+                });
+                handle
+            }
+            Err(err) => ReducerCallHandle::new(id, ReducerCallStatus::Failed(err.to_string())),
+        }
+    }
+
+    /// Resolves the oldest pending call of the reducer message `E` with the status of a result,
+    /// if the result is of a call made by this connection.
+    fn resolve<E: 'static>(
+        &self,
+        caller_connection_id: Option<ConnectionId>,
+        status: &Status,
+    ) -> Option<ReducerCallFinished> {
+        let connection_id = (*self.connection_id.lock().unwrap())?;
+        if caller_connection_id != Some(connection_id) {
+            return None;
+        }
+        let call = self
+            .pending
+            .lock()
+            .unwrap()
+            .get_mut(&TypeId::of::<E>())?
+            .pop_front()?;
+        Some(call.finish(ReducerCallStatus::from(status)))
+    }
+
+    /// Finishes the calls pending for longer than the timeout as [`ReducerCallStatus::TimedOut`].
+    fn time_out(&self) -> Vec<ReducerCallFinished> {
+        let mut finished = Vec::new();
+        for queue in self.pending.lock().unwrap().values_mut() {
+            // Calls are queued in the order they were sent, the oldest ones are first
+            while queue
+                .front()
+                .is_some_and(|call| call.sent_at.elapsed() >= self.timeout)
+            {
+                let call = queue.pop_front().unwrap();
+                finished.push(call.finish(ReducerCallStatus::TimedOut));
+            }
+        }
+        finished
+    }
+
+    /// Finishes all the pending calls as failed with `message`.
+    fn fail_pending(&self, message: &str) -> Vec<ReducerCallFinished> {
+        let mut pending = self.pending.lock().unwrap();
+        pending
+            .values_mut()
+            .flat_map(|queue| queue.drain(..))
+            .map(|call| call.finish(ReducerCallStatus::Failed(message.to_string())))
+            .collect()
+    }
+}
+
+impl PendingReducerCall {
+    fn finish(self, status: ReducerCallStatus) -> ReducerCallFinished {
+        self.handle.finish(status.clone());
+        ReducerCallFinished {
+            handle: self.handle,
+            status,
+        }
+    }
+}
+
+/// Stops following the reducer calls of the connection of the plugin with the label `L`, as it was closed
+/// or replaced, failing the calls still pending as their results won't be received.
+pub(crate) fn reset_reducer_calls<L: Send + Sync + 'static>(world: &mut World) {
+    let calls = world.resource::<ReducerCalls<L>>();
+    *calls.connection_id.lock().unwrap() = None;
+    let finished = calls.fail_pending("The connection was closed before the result was received");
+    for finished in finished {
+        world.trigger(finished);
+    }
+}
+
+/// A [`SystemParam`] to call reducers and follow their result through a [`ReducerCallHandle`].
+///
+/// Calls are matched to the results of the reducer message `E` sent by this connection, in order:
+/// calls to the same reducer made directly through [`StdbConnection::reducers`] can't be told apart from them.
+///
+/// The calls are made on the connection of the plugin with the label `L`. While there is no connection,
+/// calls fail right away, and calls still pending when the connection is closed or replaced fail as well.
+#[derive(SystemParam)]
+pub struct StdbCommands<'w, C: DbContext + Send + Sync + 'static, L: Send + Sync + 'static = ()> {
+    connection: Option<Res<'w, StdbConnection<C, L>>>,
+    calls: Res<'w, ReducerCalls<L>>,
+}
+
+impl<C: DbContext + Send + Sync + 'static, L: Send + Sync + 'static> StdbCommands<'_, C, L> {
+    /// Calls a reducer with `call`, and returns a handle following the result of the reducer message `E`.
+    ///
+    /// Panics if the reducer message was not registered with [`crate::StdbPlugin::add_reducer`].
+    ///
+    /// ```ignore
+    /// let handle = stdb.call_reducer::<SetName>(|reducers| reducers.set_name(name));
+    /// ```
+    pub fn call_reducer<E: 'static>(
+        &self,
+        call: impl FnOnce(&C::Reducers) -> spacetimedb_sdk::Result<()>,
+    ) -> ReducerCallHandle {
+        self.calls.send::<E>(|| match &self.connection {
+            Some(connection) => call(connection.reducers()),
+            None => Err(spacetimedb_sdk::Error::Disconnected),
+        })
+    }
+}
+
+/// Resolves the pending calls of the reducer message `E` from the results sent by this connection.
+pub(crate) fn resolve_reducer_calls<C, M, E, L>(
+    mut commands: Commands,
+    mut messages: MessageReader<ReducerResultMessage<E>>,
+    calls: Res<ReducerCalls<L>>,
+) where
+    C: spacetime_codegen::DbConnection<Module = M> + DbContext,
+    M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
+    E: RegisterableReducerMessage<C, M> + Send + Sync + 'static,
+    L: Send + Sync + 'static,
+{
+    for message in messages.read() {
+        let Some(event) = message.result.reducer_event() else {
+            continue;
+        };
+        if let Some(finished) = calls.resolve::<E>(event.caller_connection_id, &event.status) {
+            commands.trigger(finished);
+        }
+    }
+}
+
+/// Marks the calls pending for longer than the configured timeout as [`ReducerCallStatus::TimedOut`].
+pub(crate) fn time_out_reducer_calls<L: Send + Sync + 'static>(
+    mut commands: Commands,
+    calls: Res<ReducerCalls<L>>,
+) {
+    for finished in calls.time_out() {
+        commands.trigger(finished);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{
+        ecs::system::RunSystemOnce,
+        prelude::{On, ResMut},
+    };
+
+    struct SetName;
+    struct SetAge;
+
+    fn calls(timeout: Duration) -> ReducerCalls {
+        let calls = ReducerCalls::new(timeout);
+        calls.track::<SetName>();
+        calls.track::<SetAge>();
+        *calls.connection_id.lock().unwrap() = Some(ConnectionId::from_u128(1));
+        calls
+    }
+
+    fn send<E: 'static>(calls: &ReducerCalls) -> ReducerCallHandle {
+        calls.send::<E>(|| Ok(()))
+    }
+
+    #[test]
+    fn results_resolve_the_calls_in_order() {
+        let calls = calls(Duration::MAX);
+        let (first, second) = (send::<SetName>(&calls), send::<SetName>(&calls));
+        let other_reducer = send::<SetAge>(&calls);
+        let ours = Some(ConnectionId::from_u128(1));
+
+        let finished = calls.resolve::<SetName>(ours, &Status::Committed).unwrap();
+        assert_eq!(finished.handle.id(), first.id());
+        assert_eq!(finished.status, ReducerCallStatus::Committed);
+        assert_eq!(first.status(), ReducerCallStatus::Committed);
+        assert!(second.is_pending());
+
+        let failed = Status::Failed("name taken".into());
+        calls.resolve::<SetName>(ours, &failed).unwrap();
+        assert_eq!(
+            second.status(),
+            ReducerCallStatus::Failed("name taken".to_string())
+        );
+        assert!(other_reducer.is_pending());
+        assert!(calls.resolve::<SetName>(ours, &Status::Committed).is_none());
+    }
+
+    #[test]
+    fn results_of_other_connections_are_ignored() {
+        let calls = calls(Duration::MAX);
+        let handle = send::<SetName>(&calls);
+
+        let other = Some(ConnectionId::from_u128(2));
+        assert!(
+            calls
+                .resolve::<SetName>(other, &Status::Committed)
+                .is_none()
+        );
+        // Scheduled reducers have no caller connection
+        assert!(calls.resolve::<SetName>(None, &Status::Committed).is_none());
+        assert!(handle.is_pending());
+
+        *calls.connection_id.lock().unwrap() = None;
+        assert!(calls.resolve::<SetName>(None, &Status::Committed).is_none());
+        assert!(handle.is_pending());
+    }
+
+    #[test]
+    fn calls_that_could_not_be_sent_fail() {
+        let calls = calls(Duration::MAX);
+        let handle = calls.send::<SetName>(|| Err(spacetimedb_sdk::Error::Disconnected));
+        assert!(matches!(handle.status(), ReducerCallStatus::Failed(_)));
+        assert!(calls.time_out().is_empty());
+        assert!(calls.fail_pending("closed").is_empty());
+    }
+
+    #[test]
+    fn pending_calls_time_out() {
+        let calls = calls(Duration::ZERO);
+        let handle = send::<SetName>(&calls);
+
+        let finished = calls.time_out();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].status, ReducerCallStatus::TimedOut);
+        assert_eq!(handle.status(), ReducerCallStatus::TimedOut);
+        assert!(calls.time_out().is_empty());
+
+        let pending = ReducerCalls::<()>::new(Duration::MAX);
+        pending.track::<SetName>();
+        send::<SetName>(&pending);
+        assert!(pending.time_out().is_empty());
+    }
+
+    #[derive(Resource, Default)]
+    struct Finished(Vec<(u64, ReducerCallStatus)>);
+
+    fn world(calls: ReducerCalls) -> World {
+        let mut world = World::new();
+        world.insert_resource(calls);
+        world.init_resource::<Finished>();
+        world.add_observer(
+            |finished: On<ReducerCallFinished>, mut all: ResMut<Finished>| {
+                all.0.push((finished.handle.id(), finished.status.clone()));
+            },
+        );
+        world
+    }
+
+    #[test]
+    fn timed_out_calls_trigger_reducer_call_finished() {
+        let calls = calls(Duration::ZERO);
+        let handle = send::<SetName>(&calls);
+        let mut world = world(calls);
+
+        world.run_system_once(time_out_reducer_calls::<()>).unwrap();
+        assert_eq!(
+            world.resource::<Finished>().0,
+            vec![(handle.id(), ReducerCallStatus::TimedOut)]
+        );
+    }
+
+    #[test]
+    fn changing_the_connection_fails_the_pending_calls() {
+        let calls = calls(Duration::MAX);
+        let (first, second) = (send::<SetName>(&calls), send::<SetAge>(&calls));
+        let mut world = world(calls);
+
+        reset_reducer_calls::<()>(&mut world);
+        assert!(matches!(first.status(), ReducerCallStatus::Failed(_)));
+        assert!(matches!(second.status(), ReducerCallStatus::Failed(_)));
+        let mut finished = world.resource::<Finished>().0.clone();
+        finished.sort_by_key(|(id, _)| *id);
+        assert_eq!(
+            finished.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![first.id(), second.id()]
+        );
+
+        // Results of the new connection resolve the calls made on it, once it is identified
+        let calls = world.resource::<ReducerCalls>();
+        let third = send::<SetName>(calls);
+        let new = Some(ConnectionId::from_u128(2));
+        assert!(calls.resolve::<SetName>(new, &Status::Committed).is_none());
+        *calls.connection_id.lock().unwrap() = new;
+        assert!(calls.resolve::<SetName>(new, &Status::Committed).is_some());
+        assert_eq!(third.status(), ReducerCallStatus::Committed);
+    }
+}
@@ -1,7 +1,16 @@
-use crate::{AddMessageChannelAppExtensions, ReducerResultMessage, StdbPlugin};
-use bevy::app::App;
-use spacetimedb_sdk::__codegen as spacetime_codegen;
-use std::sync::mpsc::{Sender, channel};
+use crate::{
+    ReducerResultMessage, StdbPlugin,
+    channel_receiver::channel_to_message,
+    plugin::StdbMessageSenders,
+    reducer_calls::{ReducerCalls, resolve_reducer_calls},
+};
+use bevy::{
+    app::PreUpdate,
+    ecs::{schedule::Schedules, world::World},
+    prelude::IntoScheduleConfigs,
+};
+use spacetimedb_sdk::{__codegen as spacetime_codegen, ReducerEvent};
+use std::sync::mpsc::Sender;
 
 /// Trait for making a reducer registerable into the bevy application.
 pub trait RegisterableReducerMessage<
@@ -12,26 +21,52 @@ pub trait RegisterableReducerMessage<
 {
     /// The function that should define the stdb callback behaviour, and send a bevy message through sender.
     fn set_stdb_callback(reducers: &C::Reducers, sender: Sender<ReducerResultMessage<Self>>);
+
+    /// The event of the reducer run this message was emitted for.
+    ///
+    /// Used by [`crate::StdbCommands`] to match results with calls, calls of reducers returning `None`
+    /// stay pending until they time out.
+    fn reducer_event(&self) -> Option<&ReducerEvent<M::Reducer>> {
+        None
+    }
 }
 
 impl<
     C: spacetime_codegen::DbConnection<Module = M> + spacetimedb_sdk::DbContext,
     M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
-> StdbPlugin<C, M>
+    L: Send + Sync + 'static,
+> StdbPlugin<C, M, L>
 {
     /// Registers a reducer message <E> for the bevy application.
-    pub fn add_reducer<E: RegisterableReducerMessage<C, M> + Send + Sync + 'static>(
-        mut self,
-    ) -> Self {
-        // This callback manages the registration of the message.
-        let register_fn = move |app: &mut App, reducers: &C::Reducers| {
-            let (send, recv) = channel::<ReducerResultMessage<E>>();
-            app.add_message_channel(recv);
+    pub fn add_reducer<E: RegisterableReducerMessage<C, M> + Send + Sync + 'static>(self) -> Self {
+        // This callback registers the message when the plugin is built,
+        // along with the system resolving the calls made with StdbCommands.
+        let register_message = move |plugin: &Self, world: &mut World| {
+            plugin.message_sender::<ReducerResultMessage<E>>(world);
+            if world.resource::<ReducerCalls<L>>().track::<E>() {
+                world.resource_mut::<Schedules>().add_systems(
+                    PreUpdate,
+                    resolve_reducer_calls::<C, M, E, L>
+                        .after(channel_to_message::<ReducerResultMessage<E>>),
+                );
+            }
+        };
+        self.message_registers
+            .lock()
+            .unwrap()
+            .push(Box::new(register_message));
+
+        // This callback forwards the reducer callback to the message.
+        let register_fn = move |world: &World, reducers: &C::Reducers| {
+            let send = StdbMessageSenders::sender::<ReducerResultMessage<E>>(world);
             E::set_stdb_callback(reducers, send);
         };
 
         // The register_fn will get called once the connection is built.
-        self.reducer_registers.lock().unwrap().push(Box::new(register_fn));
+        self.reducer_registers
+            .lock()
+            .unwrap()
+            .push(Box::new(register_fn));
 
         self
     }
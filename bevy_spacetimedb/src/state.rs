@@ -0,0 +1,57 @@
+use bevy::prelude::{NextState, Res, ResMut, States};
+
+use crate::{
+    ReadStdbConnectedMessage, ReadStdbConnectionErrorMessage, ReadStdbDisconnectedMessage,
+    reconnect::ReconnectState,
+};
+
+/// The state of the connection to SpacetimeDB, registered as a Bevy [`States`] by [`crate::StdbPlugin`].
+///
+/// Use it with `OnEnter(StdbConnectionState::Connected)` or `run_if(in_state(StdbConnectionState::Connected))`
+/// to subscribe or call reducers only while connected.
+///
+/// With several plugins, it follows the connection of the one with the default label.
+/// Like any state, it needs the `StatesPlugin`, which `DefaultPlugins` include.
+#[derive(States, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StdbConnectionState {
+    /// No connection has been opened yet, or it was closed.
+    #[default]
+    Disconnected,
+    /// A connection has been opened and is waiting for the server to accept it.
+    Connecting,
+    /// The connection is established.
+    Connected,
+    /// The connection was lost and a reconnection attempt is scheduled or in progress.
+    Reconnecting,
+    /// The connection could not be established.
+    Failed,
+}
+
+/// Drives [`StdbConnectionState`] from the connection lifecycle messages.
+pub(crate) fn update_connection_state(
+    mut next_state: ResMut<NextState<StdbConnectionState>>,
+    reconnect: Option<Res<ReconnectState>>,
+    mut connected: ReadStdbConnectedMessage,
+    mut disconnected: ReadStdbDisconnectedMessage,
+    mut connection_errors: ReadStdbConnectionErrorMessage,
+) {
+    let reconnecting = reconnect.is_some_and(|state| state.next_attempt_at.is_some());
+
+    if connection_errors.read().count() > 0 {
+        next_state.set(if reconnecting {
+            StdbConnectionState::Reconnecting
+        } else {
+            StdbConnectionState::Failed
+        });
+    }
+    if disconnected.read().count() > 0 {
+        next_state.set(if reconnecting {
+            StdbConnectionState::Reconnecting
+        } else {
+            StdbConnectionState::Disconnected
+        });
+    }
+    if connected.read().count() > 0 {
+        next_state.set(StdbConnectionState::Connected);
+    }
+}
@@ -1,22 +1,132 @@
-use bevy::prelude::Resource;
+use bevy::{log::warn, platform::collections::HashMap, prelude::Resource};
 use spacetimedb_sdk::{ConnectionId, DbContext, Identity, Result};
+use std::{
+    any::{Any, TypeId},
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How long the plugin waits for the run thread of its connection to finish when the app exits,
+/// unless set with [`crate::StdbPlugin::with_shutdown_timeout`].
+pub(crate) const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A closure undoing the registration of callbacks on the view of a connection.
+pub(crate) type UnregisterFn<T> = Box<dyn FnOnce(&<T as DbContext>::DbView) + Send>;
 
 #[derive(Resource)]
 /// A connection to the SpacetimeDB server, as a Bevy resource.
 /// This struct is a wrapper around a concrete-typed `DbContext` in your auto-generated.
-pub struct StdbConnection<T: DbContext + 'static> {
+///
+/// The connection is closed when this resource is dropped, for example when it is replaced by a new
+/// connection: the table callbacks registered by the plugin are removed and the connection is disconnected.
+/// Dropping it never waits for its run thread, which can outlive the resource while it sends the reducer
+/// calls made before and finishes in the background. Only when the app exits does the plugin wait for the
+/// run thread, up to the shutdown timeout.
+///
+/// The label `L` tells apart the connections of several plugins using the same `DbConnection` type,
+/// see [`crate::StdbPlugin`].
+pub struct StdbConnection<T: DbContext + 'static, L = ()> {
     /// The underlying connection.
-    conn: &'static T,
+    conn: T,
+    /// The thread running the connection, started by the run function of the plugin.
+    run_thread: Option<JoinHandle<()>>,
+    /// How long to wait for the run thread to finish when the app exits.
+    shutdown_timeout: Duration,
+    /// Removes the callbacks registered on the connection, called when it is dropped.
+    unregister_fns: Mutex<Vec<UnregisterFn<T>>>,
+    /// The senders of the registered messages, used to deliver procedure results.
+    pub(crate) message_senders: Arc<Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>>,
+    label: PhantomData<fn() -> L>,
 }
 
-impl<T: DbContext> StdbConnection<T> {
+impl<T: DbContext, L> StdbConnection<T, L> {
     /// Create a new connection to the SpacetimeDB server.
-    pub fn new(conn: &'static T) -> Self {
-        Self { conn }
+    pub fn new(conn: T) -> Self {
+        Self {
+            conn,
+            run_thread: None,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            unregister_fns: Mutex::default(),
+            message_senders: Arc::default(),
+            label: PhantomData,
+        }
+    }
+
+    /// Use the senders of the messages registered by the plugin.
+    pub(crate) fn with_message_senders(
+        mut self,
+        message_senders: Arc<Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>>,
+    ) -> Self {
+        self.message_senders = message_senders;
+        self
+    }
+
+    /// Use the thread running the connection, joined for up to `shutdown_timeout` when the app exits.
+    pub(crate) fn with_run_thread(
+        mut self,
+        run_thread: JoinHandle<()>,
+        shutdown_timeout: Duration,
+    ) -> Self {
+        self.run_thread = Some(run_thread);
+        self.shutdown_timeout = shutdown_timeout;
+        self
+    }
+
+    /// Calls `unregister` with the view of the connection when it is dropped.
+    pub(crate) fn on_drop(&self, unregister: UnregisterFn<T>) {
+        self.unregister_fns.lock().unwrap().push(unregister);
+    }
+}
+
+impl<T: DbContext + 'static, L> StdbConnection<T, L> {
+    /// Removes the callbacks of the plugin and disconnects, returning the run thread left to finish.
+    fn close(&mut self) -> Option<JoinHandle<()>> {
+        for unregister in self.unregister_fns.get_mut().unwrap().drain(..) {
+            unregister(self.conn.db());
+        }
+
+        // The disconnection is queued after the pending reducer calls, so they are sent first
+        if self.conn.is_active() {
+            let _ = self.conn.disconnect();
+        }
+        self.run_thread.take()
+    }
+
+    /// Closes the connection and waits up to the shutdown timeout for its run thread to finish,
+    /// used when the app exits so the server sees a clean disconnection.
+    pub(crate) fn shutdown(mut self) {
+        if let Some(run_thread) = self.close() {
+            join_run_thread(run_thread, self.shutdown_timeout);
+        }
+    }
+}
+
+impl<T: DbContext + 'static, L> Drop for StdbConnection<T, L> {
+    fn drop(&mut self) {
+        // Dropped while the app runs, e.g. when replaced by a new connection: the frame doesn't wait
+        if let Some(run_thread) = self.close() {
+            let timeout = self.shutdown_timeout;
+            thread::spawn(move || join_run_thread(run_thread, timeout));
+        }
     }
 }
 
-impl<T: DbContext> StdbConnection<T> {
+/// Waits up to `timeout` for the run thread of a connection to finish, leaving it detached otherwise.
+fn join_run_thread(run_thread: JoinHandle<()>, timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    while !run_thread.is_finished() {
+        if Instant::now() >= deadline {
+            warn!("SpacetimeDB run thread did not finish within {timeout:?}, detaching it");
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    let _ = run_thread.join();
+}
+
+impl<T: DbContext, L> StdbConnection<T, L> {
     /// Access to tables in the client cache, which stores a read-only replica of the remote database state.
     pub fn db(&self) -> &T::DbView {
         self.conn.db()
@@ -27,6 +137,7 @@ impl<T: DbContext> StdbConnection<T> {
         self.conn.reducers()
     }
 
+    /// Access to procedures defined by the module.
     pub fn procedures(&self) -> &T::Procedures {
         self.conn.procedures()
     }
@@ -63,12 +174,20 @@ impl<T: DbContext> StdbConnection<T> {
     }
 
     /// Get the connection ID.
+    ///
+    /// Panics until the host has identified the connection, see [`Self::try_connection_id`].
     pub fn connection_id(&self) -> ConnectionId {
         self.conn.connection_id()
     }
 
+    /// Get the connection ID, `None` until the host has identified the connection,
+    /// which is when [`crate::StdbConnectedMessage`] is sent.
+    pub fn try_connection_id(&self) -> Option<ConnectionId> {
+        self.conn.try_connection_id()
+    }
+
     /// Access to the underlying connection, it's not recommended to use this method directly.
     pub fn conn(&self) -> &T {
-        self.conn
+        &self.conn
     }
 }
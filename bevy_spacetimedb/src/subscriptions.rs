@@ -0,0 +1,388 @@
+use std::{
+    marker::PhantomData,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+        mpsc::Sender,
+    },
+};
+
+use bevy::{
+    ecs::message::Message,
+    platform::collections::HashMap,
+    prelude::{
+        Component, DetectChanges, Entity, Insert, MessageReader, On, Query, Replace, Res, ResMut,
+        Resource,
+    },
+};
+use spacetimedb_sdk::{
+    __codegen::{self as spacetime_codegen, SubscriptionHandle},
+    ConnectionId, DbContext,
+};
+
+use crate::{
+    StdbConnectedMessage, StdbConnection, SubscriptionAppliedMessage, SubscriptionErrorMessage,
+};
+
+/// The queries [`crate::StdbPlugin`] keeps subscribed to on the connection with the label `L`.
+///
+/// Set the initial queries with [`crate::StdbPlugin::with_subscriptions`]. All the queries are subscribed to
+/// every time the connection is established, including after a reconnection, and queries added or removed
+/// at runtime are subscribed to or unsubscribed from on the next frame. Each query is subscribed to on its own,
+/// emitting a [`SubscriptionAppliedMessage`] or a [`SubscriptionErrorMessage`].
+///
+/// ```ignore
+/// fn enter_zone(mut subscriptions: ResMut<StdbSubscriptions>, zone: Res<Zone>) {
+///     subscriptions.add(format!("SELECT * FROM monster WHERE zone = {}", zone.id));
+/// }
+/// ```
+#[derive(Resource)]
+pub struct StdbSubscriptions<L = ()> {
+    queries: Vec<String>,
+    label: PhantomData<fn() -> L>,
+}
+
+impl<L> StdbSubscriptions<L> {
+    pub(crate) fn new(queries: Vec<String>) -> Self {
+        let mut subscriptions = Self {
+            queries: Vec::new(),
+            label: PhantomData,
+        };
+        for query in queries {
+            subscriptions.add(query);
+        }
+        subscriptions
+    }
+
+    /// Adds a query to subscribe to, returns `false` if it was already added.
+    pub fn add(&mut self, query: impl Into<String>) -> bool {
+        let query = query.into();
+        if self.contains(&query) {
+            return false;
+        }
+        self.queries.push(query);
+        true
+    }
+
+    /// Removes a query to unsubscribe from, returns `false` if it was not added.
+    pub fn remove(&mut self, query: &str) -> bool {
+        let len = self.queries.len();
+        self.queries.retain(|added| added != query);
+        self.queries.len() != len
+    }
+
+    /// Whether the query was added.
+    pub fn contains(&self, query: &str) -> bool {
+        self.queries.iter().any(|added| added == query)
+    }
+
+    /// The added queries, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.queries.iter().map(String::as_str)
+    }
+}
+
+/// A component subscribing to its queries on the connection with the label `L` for as long as it exists.
+///
+/// [`crate::StdbPlugin`] subscribes when the component is inserted, or once the connection is established,
+/// and unsubscribes when it is removed, replaced or its entity is despawned. Use it to scope the replicated
+/// rows to game areas:
+///
+/// ```ignore
+/// commands.spawn((
+///     Chunk { x, y },
+///     StdbSubscription::new([format!("SELECT * FROM tile WHERE chunk_x = {x} AND chunk_y = {y}")]),
+/// ));
+/// ```
+///
+/// All the queries are subscribed to together, [`StdbSubscription::state`] tells whether they were applied.
+#[derive(Component)]
+pub struct StdbSubscription<L = ()> {
+    queries: Vec<String>,
+    state: StdbSubscriptionState,
+    label: PhantomData<fn() -> L>,
+}
+
+impl StdbSubscription {
+    /// Creates a subscription to the given queries on the connection with the default label.
+    pub fn new(queries: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self::labeled(queries)
+    }
+}
+
+impl<L> StdbSubscription<L> {
+    /// Creates a subscription to the given queries on the connection with the label `L`.
+    ///
+    /// ```ignore
+    /// StdbSubscription::<Match>::labeled(["SELECT * FROM player"])
+    /// ```
+    pub fn labeled(queries: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            queries: queries.into_iter().map(Into::into).collect(),
+            state: StdbSubscriptionState::Pending,
+            label: PhantomData,
+        }
+    }
+
+    /// The queries subscribed to.
+    pub fn queries(&self) -> &[String] {
+        &self.queries
+    }
+
+    /// The state of the subscription.
+    pub fn state(&self) -> &StdbSubscriptionState {
+        &self.state
+    }
+}
+
+/// The state of a [`StdbSubscription`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StdbSubscriptionState {
+    /// The subscription was not applied yet, or the connection is not established.
+    #[default]
+    Pending,
+    /// The subscription was applied, its rows are in the client cache.
+    Applied,
+    /// The subscription failed with the given error message.
+    /// It is not subscribed to again until the next connection, or until the component is inserted again.
+    Error(String),
+}
+
+/// A change of state of the subscription of an entity, sent from the connection callbacks.
+pub(crate) struct SubscriptionStateMessage<L> {
+    entity: Entity,
+    /// The subscription the change is for, an entity gets a new one every time it subscribes.
+    id: u64,
+    state: StdbSubscriptionState,
+    label: PhantomData<fn() -> L>,
+}
+
+impl<L: Send + Sync + 'static> Message for SubscriptionStateMessage<L> {}
+
+/// The subscriptions made on the current connection with the label `L`, by query and by entity.
+#[derive(Resource)]
+pub(crate) struct ActiveSubscriptions<M: spacetime_codegen::SpacetimeModule, L> {
+    /// The connection the subscriptions were made on, set once it is established.
+    connection_id: Option<ConnectionId>,
+    handles: Mutex<HashMap<String, M::SubscriptionHandle>>,
+    next_entity_id: AtomicU64,
+    entity_handles: Mutex<HashMap<Entity, (u64, M::SubscriptionHandle)>>,
+    send_applied: Sender<SubscriptionAppliedMessage<L>>,
+    send_error: Sender<SubscriptionErrorMessage<L>>,
+    send_entity_state: Sender<SubscriptionStateMessage<L>>,
+}
+
+impl<M: spacetime_codegen::SpacetimeModule, L> ActiveSubscriptions<M, L> {
+    pub(crate) fn new(
+        send_applied: Sender<SubscriptionAppliedMessage<L>>,
+        send_error: Sender<SubscriptionErrorMessage<L>>,
+        send_entity_state: Sender<SubscriptionStateMessage<L>>,
+    ) -> Self {
+        Self {
+            connection_id: None,
+            handles: Mutex::default(),
+            next_entity_id: AtomicU64::new(0),
+            entity_handles: Mutex::default(),
+            send_applied,
+            send_error,
+            send_entity_state,
+        }
+    }
+
+    /// Whether the subscriptions can be made on the connection, i.e. it is established.
+    fn is_established<C: DbContext>(&self, connection: &StdbConnection<C, L>) -> bool {
+        // The connection is only identified once the host has accepted it
+        connection
+            .try_connection_id()
+            .is_some_and(|id| self.connection_id == Some(id))
+    }
+
+    /// Subscribes to the queries of the [`StdbSubscription`] of an entity, replacing its previous subscription.
+    fn subscribe_entity<C>(
+        &self,
+        connection: &StdbConnection<C, L>,
+        entity: Entity,
+        queries: &[String],
+    ) where
+        C: spacetime_codegen::DbConnection<Module = M>
+            + DbContext<SubscriptionBuilder = spacetime_codegen::SubscriptionBuilder<M>>,
+        M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
+        L: 'static,
+    {
+        let id = self.next_entity_id.fetch_add(1, Ordering::Relaxed);
+        let (send_applied, send_error) = (
+            self.send_entity_state.clone(),
+            self.send_entity_state.clone(),
+        );
+        let handle = connection
+            .subscription_builder()
+            .on_applied(move |_ctx| {
+                let _ = send_applied.send(SubscriptionStateMessage {
+                    entity,
+                    id,
+                    state: StdbSubscriptionState::Applied,
+                    label: PhantomData,
+                });
+            })
+            .on_error(move |_ctx, err| {
+                let _ = send_error.send(SubscriptionStateMessage {
+                    entity,
+                    id,
+                    state: StdbSubscriptionState::Error(err.to_string()),
+                    label: PhantomData,
+                });
+            })
+            .subscribe(queries.to_vec());
+
+        let previous = self
+            .entity_handles
+            .lock()
+            .unwrap()
+            .insert(entity, (id, handle));
+        if let Some((_, previous)) = previous {
+            let _ = previous.unsubscribe();
+        }
+    }
+}
+
+/// Subscribes to all the queries of [`StdbSubscriptions`] once the connection is established,
+/// then to the added ones and unsubscribes from the removed ones.
+///
+/// The [`StdbSubscription`] components are subscribed to again once a new connection is established.
+pub(crate) fn sync_subscriptions<C, M, L>(
+    connection: Option<Res<StdbConnection<C, L>>>,
+    subscriptions: Res<StdbSubscriptions<L>>,
+    mut active: ResMut<ActiveSubscriptions<M, L>>,
+    mut connected: MessageReader<StdbConnectedMessage<L>>,
+    mut entity_subscriptions: Query<(Entity, &mut StdbSubscription<L>)>,
+) where
+    C: spacetime_codegen::DbConnection<Module = M>
+        + DbContext<SubscriptionBuilder = spacetime_codegen::SubscriptionBuilder<M>>
+        + Send
+        + Sync,
+    M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
+    L: Send + Sync + 'static,
+{
+    let connected = connected.read().count() > 0;
+    let Some(connection) = connection else {
+        return;
+    };
+
+    // A new connection starts without subscriptions
+    if connected {
+        active.connection_id = connection.try_connection_id();
+        active.handles.get_mut().unwrap().clear();
+        active.entity_handles.get_mut().unwrap().clear();
+        for (entity, mut subscription) in &mut entity_subscriptions {
+            subscription.state = StdbSubscriptionState::Pending;
+            active.subscribe_entity(&connection, entity, &subscription.queries);
+        }
+    } else if !subscriptions.is_changed() {
+        return;
+    }
+    // Wait for the connection to be established before subscribing
+    if !active.is_established(&connection) {
+        return;
+    }
+
+    let mut handles = active.handles.lock().unwrap();
+    handles.retain(|query, handle| {
+        let keep = subscriptions.contains(query);
+        if !keep {
+            // Fails if the subscription already ended, e.g. after an error
+            let _ = handle.clone().unsubscribe();
+        }
+        keep
+    });
+
+    for query in subscriptions.iter() {
+        if handles.contains_key(query) {
+            continue;
+        }
+        let (send_applied, applied_query) = (active.send_applied.clone(), query.to_string());
+        let (send_error, error_query) = (active.send_error.clone(), query.to_string());
+        let handle = connection
+            .subscription_builder()
+            .on_applied(move |_ctx| {
+                let _ = send_applied.send(SubscriptionAppliedMessage {
+                    query: applied_query,
+                    label: PhantomData,
+                });
+            })
+            .on_error(move |_ctx, err| {
+                let _ = send_error.send(SubscriptionErrorMessage {
+                    query: error_query,
+                    err,
+                    label: PhantomData,
+                });
+            })
+            .subscribe(query);
+        handles.insert(query.to_string(), handle);
+    }
+}
+
+/// Subscribes to the queries of a [`StdbSubscription`] when it is inserted, if the connection is established.
+pub(crate) fn subscribe_on_insert<C, M, L>(
+    insert: On<Insert, StdbSubscription<L>>,
+    connection: Option<Res<StdbConnection<C, L>>>,
+    active: Res<ActiveSubscriptions<M, L>>,
+    mut subscriptions: Query<&mut StdbSubscription<L>>,
+) where
+    C: spacetime_codegen::DbConnection<Module = M>
+        + DbContext<SubscriptionBuilder = spacetime_codegen::SubscriptionBuilder<M>>
+        + Send
+        + Sync,
+    M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
+    L: Send + Sync + 'static,
+{
+    let Ok(mut subscription) = subscriptions.get_mut(insert.entity) else {
+        return;
+    };
+    subscription.state = StdbSubscriptionState::Pending;
+    if let Some(connection) = connection.filter(|connection| active.is_established(connection)) {
+        active.subscribe_entity(&connection, insert.entity, &subscription.queries);
+    }
+}
+
+/// Unsubscribes from the queries of a [`StdbSubscription`] when it is removed, replaced or despawned.
+pub(crate) fn unsubscribe_on_replace<M, L>(
+    replace: On<Replace, StdbSubscription<L>>,
+    active: Res<ActiveSubscriptions<M, L>>,
+) where
+    M: spacetime_codegen::SpacetimeModule,
+    L: Send + Sync + 'static,
+{
+    let removed = active
+        .entity_handles
+        .lock()
+        .unwrap()
+        .remove(&replace.entity);
+    if let Some((_, handle)) = removed {
+        // Fails if the subscription already ended, e.g. after an error
+        let _ = handle.unsubscribe();
+    }
+}
+
+/// Updates the state of the [`StdbSubscription`] components from the connection callbacks.
+pub(crate) fn update_subscription_states<M, L>(
+    mut messages: MessageReader<SubscriptionStateMessage<L>>,
+    active: Res<ActiveSubscriptions<M, L>>,
+    mut subscriptions: Query<&mut StdbSubscription<L>>,
+) where
+    M: spacetime_codegen::SpacetimeModule,
+    L: Send + Sync + 'static,
+{
+    let entity_handles = active.entity_handles.lock().unwrap();
+    for message in messages.read() {
+        // Ignore the changes of the subscriptions that were replaced since
+        let current = entity_handles
+            .get(&message.entity)
+            .is_some_and(|(id, _)| *id == message.id);
+        if !current {
+            continue;
+        }
+        if let Ok(mut subscription) = subscriptions.get_mut(message.entity) {
+            subscription.state = message.state.clone();
+        }
+    }
+}
@@ -1,15 +1,23 @@
 use std::{
-    any::TypeId,
-    sync::mpsc::{Sender, channel},
+    borrow::Cow,
+    sync::{Arc, mpsc::Sender},
 };
 
-use bevy::app::App;
-use spacetimedb_sdk::{__codegen as spacetime_codegen, Table, TableWithPrimaryKey};
+use bevy::{ecs::world::World, prelude::Resource};
+use spacetimedb_sdk::{
+    __codegen as spacetime_codegen, DbContext, Identity, Table, TableWithPrimaryKey,
+};
 
-use crate::AddMessageChannelAppExtensions;
 // Imports are marked as unused but they are useful for linking types in docs.
 // #[allow(unused_imports)]
-use crate::{DeleteMessage, InsertMessage, InsertUpdateMessage, StdbPlugin, UpdateMessage};
+use crate::{
+    DeleteMessage, InsertMessage, InsertUpdateMessage, StdbEventInfo, StdbPlugin, UpdateMessage,
+    entities::{RowChange, RowChangeMessage},
+    events::add_row_triggers,
+    plugin::StdbMessageSenders,
+    stdb_connection::UnregisterFn,
+    transactions::Transactions,
+};
 
 /// Passed into [`StdbPlugin::add_table`] to determine which table messages to register.
 #[derive(Debug, Default, Clone, Copy)]
@@ -26,6 +34,10 @@ pub struct TableMessages {
 
     /// Whether to register to a row deletion. Registers the [`DeleteMessage`] message for the table.
     pub delete: bool,
+
+    /// Whether to also trigger [`crate::RowInserted`], [`crate::RowUpdated`] and [`crate::RowDeleted`] events for the
+    /// registered messages, so they can be handled by observers.
+    pub triggers: bool,
 }
 
 impl TableMessages {
@@ -35,6 +47,7 @@ impl TableMessages {
             insert: true,
             update: true,
             delete: true,
+            triggers: false,
         }
     }
 
@@ -43,8 +56,15 @@ impl TableMessages {
             insert: true,
             update: false,
             delete: true,
+            triggers: false,
         }
     }
+
+    /// Also trigger the row events of the registered messages, see [`TableMessages::triggers`].
+    pub fn with_triggers(mut self) -> Self {
+        self.triggers = true;
+        self
+    }
 }
 
 /// Passed into [`StdbPlugin::add_table_without_pk`] to determine which table messages to register.
@@ -55,6 +75,8 @@ pub struct TableMessagesWithoutPrimaryKey {
     pub insert: bool,
     /// Same as [`TableMessages::delete`]
     pub delete: bool,
+    /// Same as [`TableMessages::triggers`]
+    pub triggers: bool,
 }
 
 impl TableMessagesWithoutPrimaryKey {
@@ -63,219 +85,1036 @@ impl TableMessagesWithoutPrimaryKey {
         Self {
             insert: true,
             delete: true,
+            triggers: false,
         }
     }
+
+    /// Same as [`TableMessages::with_triggers`]
+    pub fn with_triggers(mut self) -> Self {
+        self.triggers = true;
+        self
+    }
+}
+
+/// Gets a table from the view of a connection to attach the callbacks of the plugin, such as the
+/// `RemoteTables::player` accessor of the generated bindings.
+///
+/// Implemented for the functions returning a table handle that borrows the view, the handle is only used
+/// while the view is borrowed so it never outlives its connection.
+pub trait TableAccessor<V>: Send + Sync + 'static {
+    /// The type of the rows of the table.
+    type Row: 'static;
+    /// The `EventContext` passed to the row callbacks.
+    type EventContext: 'static;
+    /// Identifies a callback registered with [`TableAccessor::on_insert`].
+    type InsertCallbackId: Send + 'static;
+    /// Identifies a callback registered with [`TableAccessor::on_delete`].
+    type DeleteCallbackId: Send + 'static;
+
+    /// Same as [`Table::on_insert`], on the table of the view.
+    fn on_insert(
+        &self,
+        db: &V,
+        callback: impl FnMut(&Self::EventContext, &Self::Row) + Send + 'static,
+    ) -> Self::InsertCallbackId;
+
+    /// Same as [`Table::remove_on_insert`], on the table of the view.
+    fn remove_on_insert(&self, db: &V, callback: Self::InsertCallbackId);
+
+    /// Same as [`Table::on_delete`], on the table of the view.
+    fn on_delete(
+        &self,
+        db: &V,
+        callback: impl FnMut(&Self::EventContext, &Self::Row) + Send + 'static,
+    ) -> Self::DeleteCallbackId;
+
+    /// Same as [`Table::remove_on_delete`], on the table of the view.
+    fn remove_on_delete(&self, db: &V, callback: Self::DeleteCallbackId);
+}
+
+/// A [`TableAccessor`] of a table with a primary key.
+pub trait TableWithPrimaryKeyAccessor<V>: TableAccessor<V> {
+    /// Identifies a callback registered with [`TableWithPrimaryKeyAccessor::on_update`].
+    type UpdateCallbackId: Send + 'static;
+
+    /// Same as [`TableWithPrimaryKey::on_update`], on the table of the view.
+    fn on_update(
+        &self,
+        db: &V,
+        callback: impl FnMut(&Self::EventContext, &Self::Row, &Self::Row) + Send + 'static,
+    ) -> Self::UpdateCallbackId;
+
+    /// Same as [`TableWithPrimaryKey::remove_on_update`], on the table of the view.
+    fn remove_on_update(&self, db: &V, callback: Self::UpdateCallbackId);
+}
+
+/// A function returning a value that may borrow its argument, implemented for any `Fn(&'a V) -> T`.
+///
+/// Names the return type of table accessors for every lifetime of the view.
+pub trait BorrowingFn<'a, V: 'a> {
+    /// The returned value.
+    type Output;
+
+    /// Calls the function.
+    fn apply(&self, value: &'a V) -> Self::Output;
+}
+
+impl<'a, V: 'a, F: Fn(&'a V) -> T, T> BorrowingFn<'a, V> for F {
+    type Output = T;
+
+    fn apply(&self, value: &'a V) -> T {
+        self(value)
+    }
+}
+
+impl<V, F, TRow, Ctx, I, D> TableAccessor<V> for F
+where
+    F: for<'a> BorrowingFn<'a, V> + Send + Sync + 'static,
+    // The callbacks registered on the table of one borrow are removed from the table of another
+    for<'a> <F as BorrowingFn<'a, V>>::Output:
+        Table<Row = TRow, EventContext = Ctx, InsertCallbackId = I, DeleteCallbackId = D>,
+    TRow: 'static,
+    Ctx: 'static,
+    I: Send + 'static,
+    D: Send + 'static,
+{
+    type Row = TRow;
+    type EventContext = Ctx;
+    type InsertCallbackId = I;
+    type DeleteCallbackId = D;
+
+    fn on_insert(
+        &self,
+        db: &V,
+        callback: impl FnMut(&Self::EventContext, &Self::Row) + Send + 'static,
+    ) -> Self::InsertCallbackId {
+        self.apply(db).on_insert(callback)
+    }
+
+    fn remove_on_insert(&self, db: &V, callback: Self::InsertCallbackId) {
+        self.apply(db).remove_on_insert(callback)
+    }
+
+    fn on_delete(
+        &self,
+        db: &V,
+        callback: impl FnMut(&Self::EventContext, &Self::Row) + Send + 'static,
+    ) -> Self::DeleteCallbackId {
+        self.apply(db).on_delete(callback)
+    }
+
+    fn remove_on_delete(&self, db: &V, callback: Self::DeleteCallbackId) {
+        self.apply(db).remove_on_delete(callback)
+    }
+}
+
+impl<V, F, U> TableWithPrimaryKeyAccessor<V> for F
+where
+    F: TableAccessor<V> + for<'a> BorrowingFn<'a, V>,
+    for<'a> <F as BorrowingFn<'a, V>>::Output: TableWithPrimaryKey<
+            Row = <F as TableAccessor<V>>::Row,
+            EventContext = <F as TableAccessor<V>>::EventContext,
+            UpdateCallbackId = U,
+        >,
+    U: Send + 'static,
+{
+    type UpdateCallbackId = U;
+
+    fn on_update(
+        &self,
+        db: &V,
+        callback: impl FnMut(&Self::EventContext, &Self::Row, &Self::Row) + Send + 'static,
+    ) -> Self::UpdateCallbackId {
+        self.apply(db).on_update(callback)
+    }
+
+    fn remove_on_update(&self, db: &V, callback: Self::UpdateCallbackId) {
+        self.apply(db).remove_on_update(callback)
+    }
 }
 
 impl<
     C: spacetime_codegen::DbConnection<Module = M> + spacetimedb_sdk::DbContext,
     M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
-> StdbPlugin<C, M>
+    L: Send + Sync + 'static,
+> StdbPlugin<C, M, L>
 {
     /// Registers a table for the bevy application with all messages enabled.
-    pub fn add_table<TRow, TTable, F>(self, accessor: F) -> Self
+    ///
+    /// Use [`StdbPlugin::add_partial_table`] with [`TableMessages::with_triggers`] to also trigger row events.
+    pub fn add_table<TRow, A>(self, accessor: A) -> Self
     where
         TRow: Send + Sync + Clone + 'static,
-        TTable: Table<Row = TRow> + TableWithPrimaryKey<Row = TRow>,
-        F: 'static + Send + Sync + Fn(&'static C::DbView) -> TTable,
+        A: TableWithPrimaryKeyAccessor<C::DbView, Row = TRow, EventContext = M::EventContext>,
     {
         self.add_partial_table(accessor, TableMessages::all())
     }
 
     ///Registers a table for the bevy application with the specified messages in the `messages` parameter.
-    pub fn add_partial_table<TRow, TTable, F>(
-        mut self,
-        accessor: F,
+    pub fn add_partial_table<TRow, A>(self, accessor: A, messages: TableMessages) -> Self
+    where
+        TRow: Send + Sync + Clone + 'static,
+        A: TableWithPrimaryKeyAccessor<C::DbView, Row = TRow, EventContext = M::EventContext>,
+    {
+        self.add_table_with_map(accessor, messages, RowMap::all())
+    }
+
+    /// Same as [`StdbPlugin::add_partial_table`], but only the rows for which `predicate` returns `true`
+    /// are sent, the predicate being evaluated on the connection thread before the messages are sent.
+    ///
+    /// An update is sent as an update if both rows are kept, as a deletion if only the old row is kept,
+    /// and as an insertion if only the new one is.
+    ///
+    /// ```ignore
+    /// plugin.add_table_filtered(RemoteTables::monster, TableMessages::all(), |monster: &Monster| monster.zone == 1)
+    /// ```
+    pub fn add_table_filtered<TRow, A>(
+        self,
+        accessor: A,
         messages: TableMessages,
+        predicate: impl Fn(&TRow) -> bool + Send + Sync + 'static,
     ) -> Self
     where
         TRow: Send + Sync + Clone + 'static,
-        TTable: Table<Row = TRow> + TableWithPrimaryKey<Row = TRow>,
-        F: 'static + Send + Sync + Fn(&'static C::DbView) -> TTable,
+        A: TableWithPrimaryKeyAccessor<C::DbView, Row = TRow, EventContext = M::EventContext>,
     {
-        // A closure that sets up messages for the table
-        let register = move |plugin: &Self, app: &mut App, db: &'static C::DbView| {
-            let table = accessor(db);
-            if messages.insert {
-                plugin.on_insert(app, &table);
-            }
-            if messages.delete {
-                plugin.on_delete(app, &table);
-            }
-            if messages.update {
-                plugin.on_update(app, &table);
-            }
-            if messages.update && messages.insert {
-                plugin.on_insert_update(app, &table);
+        let map = RowMap::filtered(move |_ctx, row| predicate(row));
+        self.add_table_with_map(accessor, messages, map)
+    }
+
+    /// Same as [`StdbPlugin::add_table_filtered`], but the predicate is also given the `Identity` of the connection,
+    /// to keep the rows owned by the client.
+    ///
+    /// ```ignore
+    /// plugin.add_table_filtered_by_identity(RemoteTables::item, TableMessages::all(), |item: &Item, identity| {
+    ///     item.owner == identity
+    /// })
+    /// ```
+    pub fn add_table_filtered_by_identity<TRow, A>(
+        self,
+        accessor: A,
+        messages: TableMessages,
+        predicate: impl Fn(&TRow, Identity) -> bool + Send + Sync + 'static,
+    ) -> Self
+    where
+        M::EventContext: DbContext,
+        TRow: Send + Sync + Clone + 'static,
+        A: TableWithPrimaryKeyAccessor<C::DbView, Row = TRow, EventContext = M::EventContext>,
+    {
+        // The identity is received before any row, as rows only arrive once subscribed
+        let map = RowMap::filtered(move |ctx: &M::EventContext, row| {
+            ctx.try_identity()
+                .is_some_and(|identity| predicate(row, identity))
+        });
+        self.add_table_with_map(accessor, messages, map)
+    }
+
+    /// Registers a table with all messages enabled, for rows projected by `map` into the lighter type `T`:
+    /// `T` is cloned into the messages instead of the full rows, the rows being mapped on the connection thread.
+    ///
+    /// The messages are those of `T`, such as [`InsertMessage<T>`].
+    ///
+    /// ```ignore
+    /// #[derive(Clone)]
+    /// struct PlayerPosition { id: u64, x: f32, y: f32 }
+    ///
+    /// plugin.add_table_mapped(RemoteTables::player, |player: &Player| PlayerPosition {
+    ///     id: player.id,
+    ///     x: player.x,
+    ///     y: player.y,
+    /// })
+    ///
+    /// fn on_player_moved(mut messages: ReadUpdateMessage<PlayerPosition>) {}
+    /// ```
+    pub fn add_table_mapped<T, TRow, A>(
+        self,
+        accessor: A,
+        map: impl Fn(&TRow) -> T + Send + Sync + 'static,
+    ) -> Self
+    where
+        T: Send + Sync + Clone + 'static,
+        TRow: Send + Sync + Clone + 'static,
+        A: TableWithPrimaryKeyAccessor<C::DbView, Row = TRow, EventContext = M::EventContext>,
+    {
+        self.add_partial_table_mapped(accessor, TableMessages::all(), map)
+    }
+
+    /// Same as [`StdbPlugin::add_table_mapped`], with the specified messages in the `messages` parameter.
+    pub fn add_partial_table_mapped<T, TRow, A>(
+        self,
+        accessor: A,
+        messages: TableMessages,
+        map: impl Fn(&TRow) -> T + Send + Sync + 'static,
+    ) -> Self
+    where
+        T: Send + Sync + Clone + 'static,
+        TRow: Send + Sync + Clone + 'static,
+        A: TableWithPrimaryKeyAccessor<C::DbView, Row = TRow, EventContext = M::EventContext>,
+    {
+        self.add_table_with_map(accessor, messages, RowMap::projected(map))
+    }
+
+    /// Registers a table whose messages are sent for the rows returned by `map`, of type `T`.
+    fn add_table_with_map<T, TRow, A>(
+        self,
+        accessor: A,
+        messages: TableMessages,
+        map: RowMap<M::EventContext, TRow, T>,
+    ) -> Self
+    where
+        T: Send + Sync + Clone + 'static,
+        TRow: Send + Sync + Clone + 'static,
+        A: TableWithPrimaryKeyAccessor<C::DbView, Row = TRow, EventContext = M::EventContext>,
+    {
+        // A closure that registers the messages of the table when the plugin is built
+        let register_messages = move |plugin: &Self, world: &mut World| {
+            TableSenders::<T>::new(plugin, world, messages).register(world);
+            if messages.triggers {
+                add_row_triggers::<T>(world, messages);
             }
         };
+        self.message_registers
+            .lock()
+            .unwrap()
+            .push(Box::new(register_messages));
+
+        // A closure that forwards the table callbacks to the messages, on every new connection,
+        // and returns the closure removing them when the connection is dropped
+        let accessor = Arc::new(accessor);
+        let register = move |world: &World, db: &C::DbView| {
+            let senders = TableSenders::<T>::registered(world, messages);
+            let unregister_update = senders.clone().on_update(&*accessor, db, map.clone());
+            let unregister_insert_delete = senders.on_insert_delete(&*accessor, db, map.clone());
+            let unregister_transactions =
+                world
+                    .get_resource::<Transactions<M, L>>()
+                    .map(|transactions| {
+                        (
+                            transactions.on_update(&*accessor, db, map.clone()),
+                            transactions.on_insert_delete(&*accessor, db, map.clone()),
+                        )
+                    });
+
+            let accessor = Arc::clone(&accessor);
+            Box::new(move |db: &C::DbView| {
+                unregister_update(&*accessor, db);
+                unregister_insert_delete(&*accessor, db);
+                if let Some((unregister_update, unregister_insert_delete)) = unregister_transactions
+                {
+                    unregister_update(&*accessor, db);
+                    unregister_insert_delete(&*accessor, db);
+                }
+            }) as UnregisterFn<C>
+        };
 
         // Store this table, and later when the plugin is built, call them on .
-        self.table_registers.lock().unwrap().push(Box::new(register));
+        self.table_registers
+            .lock()
+            .unwrap()
+            .push(Box::new(register));
 
         self
     }
 
     /// Registers a table without primary key for the bevy application with all messages enabled.
-    pub fn add_table_without_pk<TRow, TTable, F>(self, accessor: F) -> Self
+    pub fn add_table_without_pk<TRow, A>(self, accessor: A) -> Self
     where
         TRow: Send + Sync + Clone + 'static,
-        TTable: Table<Row = TRow>,
-        F: 'static + Send + Sync + Fn(&'static C::DbView) -> TTable,
+        A: TableAccessor<C::DbView, Row = TRow, EventContext = M::EventContext>,
     {
         self.add_partial_table_without_pk(accessor, TableMessagesWithoutPrimaryKey::all())
     }
 
     ///Registers a table without primary key for the bevy application with the specified messages in the `messages` parameter.
-    pub fn add_partial_table_without_pk<TRow, TTable, F>(
+    pub fn add_partial_table_without_pk<TRow, A>(
         self,
-        accessor: F,
+        accessor: A,
         messages: TableMessagesWithoutPrimaryKey,
     ) -> Self
     where
         TRow: Send + Sync + Clone + 'static,
-        TTable: Table<Row = TRow>,
-        F: 'static + Send + Sync + Fn(&'static C::DbView) -> TTable,
+        A: TableAccessor<C::DbView, Row = TRow, EventContext = M::EventContext>,
     {
-        // A closure that sets up messages for the table
-        let register = move |plugin: &Self, app: &mut App, db: &'static C::DbView| {
-            let table = accessor(db);
-            if messages.insert {
-                plugin.on_insert(app, &table);
-            }
-            if messages.delete {
-                plugin.on_delete(app, &table);
+        let messages = TableMessages {
+            insert: messages.insert,
+            update: false,
+            delete: messages.delete,
+            triggers: messages.triggers,
+        };
+
+        // A closure that registers the messages of the table when the plugin is built
+        let register_messages = move |plugin: &Self, world: &mut World| {
+            TableSenders::<TRow>::new(plugin, world, messages).register(world);
+            if messages.triggers {
+                add_row_triggers::<TRow>(world, messages);
             }
         };
+        self.message_registers
+            .lock()
+            .unwrap()
+            .push(Box::new(register_messages));
+
+        // A closure that forwards the table callbacks to the messages, on every new connection,
+        // and returns the closure removing them when the connection is dropped
+        let accessor = Arc::new(accessor);
+        let register = move |world: &World, db: &C::DbView| {
+            let unregister = TableSenders::<TRow>::registered(world, messages).on_insert_delete(
+                &*accessor,
+                db,
+                RowMap::all(),
+            );
+            let unregister_transactions = world
+                .get_resource::<Transactions<M, L>>()
+                .map(|transactions| transactions.on_insert_delete(&*accessor, db, RowMap::all()));
+
+            let accessor = Arc::clone(&accessor);
+            Box::new(move |db: &C::DbView| {
+                unregister(&*accessor, db);
+                if let Some(unregister_transactions) = unregister_transactions {
+                    unregister_transactions(&*accessor, db);
+                }
+            }) as UnregisterFn<C>
+        };
         // Store this table, and later when the plugin is built, call them on .
-        self.table_registers.lock().unwrap().push(Box::new(register));
+        self.table_registers
+            .lock()
+            .unwrap()
+            .push(Box::new(register));
 
         self
     }
+}
+
+/// The senders of the messages registered for the rows of a table.
+///
+/// Every registration of a table forwards the table callbacks to its own senders, the ones of all
+/// registrations are also merged into a resource so rows can be sent without a connection, see [`crate::injection`].
+#[derive(Resource)]
+pub(crate) struct TableSenders<TRow> {
+    insert: Option<Sender<InsertMessage<TRow>>>,
+    update: Option<Sender<UpdateMessage<TRow>>>,
+    delete: Option<Sender<DeleteMessage<TRow>>>,
+    insert_update: Option<Sender<InsertUpdateMessage<TRow>>>,
+    row_changes: Option<Sender<RowChangeMessage<TRow>>>,
+}
+
+impl<TRow> Default for TableSenders<TRow> {
+    fn default() -> Self {
+        Self {
+            insert: None,
+            update: None,
+            delete: None,
+            insert_update: None,
+            row_changes: None,
+        }
+    }
+}
+
+impl<TRow> Clone for TableSenders<TRow> {
+    fn clone(&self) -> Self {
+        Self {
+            insert: self.insert.clone(),
+            update: self.update.clone(),
+            delete: self.delete.clone(),
+            insert_update: self.insert_update.clone(),
+            row_changes: self.row_changes.clone(),
+        }
+    }
+}
 
-    /// Register a Bevy message of type InsertMessage<TRow> for the `on_insert` message on the provided table.
-    fn on_insert<TRow>(&self, app: &mut App, table: &impl Table<Row = TRow>) -> &Self
+impl<TRow: Send + Sync + Clone + 'static> TableSenders<TRow> {
+    /// Returns the senders of the given table messages, registering their channels on first use.
+    pub(crate) fn new<C, M, L>(
+        plugin: &StdbPlugin<C, M, L>,
+        world: &mut World,
+        messages: TableMessages,
+    ) -> Self
     where
-        TRow: Send + Sync + Clone + 'static,
+        C: spacetime_codegen::DbConnection<Module = M> + spacetimedb_sdk::DbContext,
+        M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
     {
-        let type_id = TypeId::of::<InsertMessage<TRow>>();
-
-        let mut map = self.message_senders.lock().unwrap();
-
-        let sender = map
-            .entry(type_id)
-            .or_insert_with(|| {
-                let (send, recv) = channel::<InsertMessage<TRow>>();
-                app.add_message_channel(recv);
-                Box::new(send)
-            })
-            .downcast_ref::<Sender<InsertMessage<TRow>>>()
-            .expect("Sender type mismatch")
-            .clone();
-
-        table.on_insert(move |_ctx, row| {
-            let message = InsertMessage { row: row.clone() };
-            let _ = sender.send(message);
-        });
+        Self {
+            insert: messages
+                .insert
+                .then(|| plugin.message_sender::<InsertMessage<TRow>>(world)),
+            update: messages
+                .update
+                .then(|| plugin.message_sender::<UpdateMessage<TRow>>(world)),
+            delete: messages
+                .delete
+                .then(|| plugin.message_sender::<DeleteMessage<TRow>>(world)),
+            insert_update: (messages.insert && messages.update)
+                .then(|| plugin.message_sender::<InsertUpdateMessage<TRow>>(world)),
+            row_changes: None,
+        }
+    }
 
-        self
+    /// Returns the senders of the given table messages, registered when the plugin was built.
+    pub(crate) fn registered(world: &World, messages: TableMessages) -> Self {
+        Self {
+            insert: messages
+                .insert
+                .then(|| StdbMessageSenders::sender::<InsertMessage<TRow>>(world)),
+            update: messages
+                .update
+                .then(|| StdbMessageSenders::sender::<UpdateMessage<TRow>>(world)),
+            delete: messages
+                .delete
+                .then(|| StdbMessageSenders::sender::<DeleteMessage<TRow>>(world)),
+            insert_update: (messages.insert && messages.update)
+                .then(|| StdbMessageSenders::sender::<InsertUpdateMessage<TRow>>(world)),
+            row_changes: None,
+        }
     }
 
-    /// Register a Bevy message of type DeleteMessage<TRow> for the `on_delete` message on the provided table.
-    fn on_delete<TRow>(&self, app: &mut App, table: &impl Table<Row = TRow>) -> &Self
+    /// Returns the sender of the row changes mirrored into entities, registering its channel on first use.
+    pub(crate) fn row_changes<C, M, L>(plugin: &StdbPlugin<C, M, L>, world: &mut World) -> Self
     where
-        TRow: Send + Sync + Clone + 'static,
+        C: spacetime_codegen::DbConnection<Module = M> + spacetimedb_sdk::DbContext,
+        M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
     {
-        let type_id = TypeId::of::<DeleteMessage<TRow>>();
-
-        let mut map = self.message_senders.lock().unwrap();
-        let sender = map
-            .entry(type_id)
-            .or_insert_with(|| {
-                let (send, recv) = channel::<DeleteMessage<TRow>>();
-                app.add_message_channel(recv);
-                Box::new(send)
-            })
-            .downcast_ref::<Sender<DeleteMessage<TRow>>>()
-            .expect("Sender type mismatch")
-            .clone();
-
-        table.on_delete(move |_ctx, row| {
-            let message = DeleteMessage { row: row.clone() };
-            let _ = sender.send(message);
-        });
+        Self {
+            row_changes: Some(plugin.message_sender::<RowChangeMessage<TRow>>(world)),
+            ..Default::default()
+        }
+    }
 
-        self
+    /// Returns the sender of the row changes mirrored into entities, registered when the plugin was built.
+    pub(crate) fn registered_row_changes(world: &World) -> Self {
+        Self {
+            row_changes: Some(StdbMessageSenders::sender::<RowChangeMessage<TRow>>(world)),
+            ..Default::default()
+        }
+    }
+
+    /// Merges these senders into the [`TableSenders`] resource of the table.
+    pub(crate) fn register(self, world: &mut World) {
+        let mut senders = world.get_resource_or_init::<TableSenders<TRow>>();
+        senders.insert = senders.insert.take().or(self.insert);
+        senders.update = senders.update.take().or(self.update);
+        senders.delete = senders.delete.take().or(self.delete);
+        senders.insert_update = senders.insert_update.take().or(self.insert_update);
+        senders.row_changes = senders.row_changes.take().or(self.row_changes);
     }
 
-    /// Register a Bevy message of type UpdateMessage<TRow> for the `on_update` message on the provided table.
-    fn on_update<TRow, TTable>(&self, app: &mut App, table: &TTable) -> &Self
+    /// Sends the messages of a row insertion.
+    pub(crate) fn insert(&self, event: &StdbEventInfo, row: Cow<'_, TRow>) {
+        let mut row = SharedRow::new(
+            row,
+            [
+                self.insert.is_some(),
+                self.insert_update.is_some(),
+                self.row_changes.is_some(),
+            ],
+        );
+        if let Some(sender) = &self.insert {
+            let _ = sender.send(InsertMessage {
+                row: row.take(),
+                event: event.clone(),
+            });
+        }
+        if let Some(sender) = &self.insert_update {
+            let _ = sender.send(InsertUpdateMessage {
+                old: None,
+                new: row.take(),
+                event: event.clone(),
+            });
+        }
+        if let Some(sender) = &self.row_changes {
+            let _ = sender.send(RowChangeMessage(RowChange::Insert(row.take())));
+        }
+    }
+
+    /// Sends the messages of a row update.
+    pub(crate) fn update(&self, event: &StdbEventInfo, old: Cow<'_, TRow>, new: Cow<'_, TRow>) {
+        let senders = [
+            self.update.is_some(),
+            self.insert_update.is_some(),
+            self.row_changes.is_some(),
+        ];
+        let (mut old, mut new) = (SharedRow::new(old, senders), SharedRow::new(new, senders));
+        if let Some(sender) = &self.update {
+            let _ = sender.send(UpdateMessage {
+                old: old.take(),
+                new: new.take(),
+                event: event.clone(),
+            });
+        }
+        if let Some(sender) = &self.insert_update {
+            let _ = sender.send(InsertUpdateMessage {
+                old: Some(old.take()),
+                new: new.take(),
+                event: event.clone(),
+            });
+        }
+        if let Some(sender) = &self.row_changes {
+            let _ = sender.send(RowChangeMessage(RowChange::Update(old.take(), new.take())));
+        }
+    }
+
+    /// Sends the messages of a row deletion.
+    pub(crate) fn delete(&self, event: &StdbEventInfo, row: Cow<'_, TRow>) {
+        let mut row = SharedRow::new(row, [self.delete.is_some(), self.row_changes.is_some()]);
+        if let Some(sender) = &self.delete {
+            let _ = sender.send(DeleteMessage {
+                row: row.take(),
+                event: event.clone(),
+            });
+        }
+        if let Some(sender) = &self.row_changes {
+            let _ = sender.send(RowChangeMessage(RowChange::Delete(row.take())));
+        }
+    }
+
+    /// Tells the system mirroring the table that the connection of the rows was dropped.
+    pub(crate) fn clear_row_changes(&self) {
+        if let Some(sender) = &self.row_changes {
+            let _ = sender.send(RowChangeMessage(RowChange::Clear));
+        }
+    }
+
+    /// Forwards the `on_insert` and `on_delete` callbacks of the table to the messages of the rows returned by `map`,
+    /// returns a closure removing them.
+    pub(crate) fn on_insert_delete<V, A>(
+        self,
+        accessor: &A,
+        db: &V,
+        map: RowMap<A::EventContext, A::Row, TRow>,
+    ) -> impl FnOnce(&A, &V) + Send + use<TRow, V, A>
     where
-        TRow: Send + Sync + Clone + 'static,
-        TTable: Table<Row = TRow> + TableWithPrimaryKey<Row = TRow>,
+        A: TableAccessor<V, EventContext: RowEventContext>,
     {
-        let type_id = TypeId::of::<UpdateMessage<TRow>>();
-
-        let mut map = self.message_senders.lock().unwrap();
-        let sender = map
-            .entry(type_id)
-            .or_insert_with(|| {
-                let (send, recv) = channel::<UpdateMessage<TRow>>();
-                app.add_message_channel(recv);
-                Box::new(send)
-            })
-            .downcast_ref::<Sender<UpdateMessage<TRow>>>()
-            .expect("Sender type mismatch")
-            .clone();
-
-        table.on_update(move |_ctx, old, new| {
-            let message = UpdateMessage {
-                old: old.clone(),
-                new: new.clone(),
-            };
-            let _ = sender.send(message);
+        let (senders, insert_map) = (self.clone(), map.clone());
+        let insert = accessor.on_insert(db, move |ctx, row| {
+            if let Some(row) = insert_map.map(ctx, row) {
+                senders.insert(&ctx.event_info(), row);
+            }
         });
-
-        self
+        let delete = accessor.on_delete(db, move |ctx, row| {
+            if let Some(row) = map.map(ctx, row) {
+                self.delete(&ctx.event_info(), row);
+            }
+        });
+        move |accessor, db| {
+            accessor.remove_on_insert(db, insert);
+            accessor.remove_on_delete(db, delete);
+        }
     }
 
-    /// Register a Bevy message of type InsertUpdateMessage<TRow> for the `on_insert` and `on_update` messages on the provided table.
-    fn on_insert_update<TRow, TTable>(&self, app: &mut App, table: &TTable) -> &Self
+    /// Forwards the `on_update` callback of the table to the messages of the rows returned by `map`,
+    /// returns a closure removing it.
+    pub(crate) fn on_update<V, A>(
+        self,
+        accessor: &A,
+        db: &V,
+        map: RowMap<A::EventContext, A::Row, TRow>,
+    ) -> impl FnOnce(&A, &V) + Send + use<TRow, V, A>
     where
-        TRow: Send + Sync + Clone + 'static,
-        TTable: Table<Row = TRow> + TableWithPrimaryKey<Row = TRow>,
+        A: TableWithPrimaryKeyAccessor<V, EventContext: RowEventContext>,
     {
-        let type_id = TypeId::of::<InsertUpdateMessage<TRow>>();
-
-        let mut map = self.message_senders.lock().unwrap();
-        let send = map
-            .entry(type_id)
-            .or_insert_with(|| {
-                let (send, recv) = channel::<InsertUpdateMessage<TRow>>();
-                app.add_message_channel(recv);
-                Box::new(send)
-            })
-            .downcast_ref::<Sender<InsertUpdateMessage<TRow>>>()
-            .expect("Sender type mismatch")
-            .clone();
-
-        let send_update = send.clone();
-        table.on_update(move |_ctx, old, new| {
-            let message = InsertUpdateMessage {
-                old: Some(old.clone()),
-                new: new.clone(),
-            };
-            let _ = send_update.send(message);
+        let update = accessor.on_update(db, move |ctx, old, new| {
+            self.send_update(&ctx.event_info(), map.map(ctx, old), map.map(ctx, new));
         });
+        move |accessor, db| accessor.remove_on_update(db, update)
+    }
 
-        table.on_insert(move |_ctx, row| {
-            let message = InsertUpdateMessage {
-                old: None,
-                new: row.clone(),
-            };
-            let _ = send.send(message);
-        });
+    /// Sends the messages of a row update whose rows may be left out by the map of the table: an update
+    /// if both rows are kept, a deletion if only the old row is, and an insertion if only the new one is.
+    fn send_update(
+        &self,
+        event: &StdbEventInfo,
+        old: Option<Cow<'_, TRow>>,
+        new: Option<Cow<'_, TRow>>,
+    ) {
+        match (old, new) {
+            (Some(old), Some(new)) => self.update(event, old, new),
+            (Some(old), None) => self.delete(event, old),
+            (None, Some(new)) => self.insert(event, new),
+            (None, None) => {}
+        }
+    }
+}
 
-        self
+/// A row sent to several messages, cloned for all of them but the last one, which takes an owned row.
+struct SharedRow<'a, TRow: Clone> {
+    row: Option<Cow<'a, TRow>>,
+    remaining: usize,
+}
+
+impl<'a, TRow: Clone> SharedRow<'a, TRow> {
+    /// Shares `row` between the messages whose sender is enabled.
+    fn new<const N: usize>(row: Cow<'a, TRow>, senders: [bool; N]) -> Self {
+        Self {
+            row: Some(row),
+            remaining: senders.into_iter().filter(|enabled| *enabled).count(),
+        }
+    }
+
+    /// Returns the row of the next message.
+    fn take(&mut self) -> TRow {
+        self.remaining -= 1;
+        match self.remaining {
+            0 => self.row.take().unwrap().into_owned(),
+            _ => TRow::clone(self.row.as_ref().unwrap()),
+        }
+    }
+}
+
+/// The context of the row callbacks, telling which event changed the rows.
+pub(crate) trait RowEventContext {
+    fn event_info(&self) -> StdbEventInfo;
+}
+
+impl<Ctx> RowEventContext for Ctx
+where
+    Ctx: spacetime_codegen::EventContext,
+    Ctx::Module: spacetime_codegen::SpacetimeModule<EventContext = Ctx>,
+{
+    fn event_info(&self) -> StdbEventInfo {
+        StdbEventInfo::new::<Ctx::Module>(self.event())
+    }
+}
+
+/// Turns the rows of a table into the rows `T` of its messages, evaluated in the table callbacks
+/// given their context `Ctx`.
+///
+/// Returns `None` for the rows left out by [`StdbPlugin::add_table_filtered`], and the projection of the rows
+/// registered with [`StdbPlugin::add_table_mapped`]. Kept rows are borrowed and projections are owned,
+/// the messages clone them only when there are several of them.
+pub(crate) struct RowMap<Ctx, TRow, T: Clone>(
+    #[allow(clippy::type_complexity)]
+    Arc<dyn for<'a> Fn(&Ctx, &'a TRow) -> Option<Cow<'a, T>> + Send + Sync>,
+);
+
+impl<Ctx, TRow, T: Clone> Clone for RowMap<Ctx, TRow, T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<Ctx, TRow, T: Clone> RowMap<Ctx, TRow, T> {
+    pub(crate) fn new(
+        map: impl for<'a> Fn(&Ctx, &'a TRow) -> Option<Cow<'a, T>> + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(map))
+    }
+
+    /// A map projecting every row with `map`.
+    pub(crate) fn projected(map: impl Fn(&TRow) -> T + Send + Sync + 'static) -> Self {
+        Self::new(move |_ctx, row| Some(Cow::Owned(map(row))))
+    }
+
+    /// Returns the row of the messages for the row of the table, if it is kept.
+    pub(crate) fn map<'a>(&self, ctx: &Ctx, row: &'a TRow) -> Option<Cow<'a, T>> {
+        (self.0)(ctx, row)
+    }
+}
+
+impl<Ctx, TRow: Clone> RowMap<Ctx, TRow, TRow> {
+    /// A map keeping all the rows as they are.
+    pub(crate) fn all() -> Self {
+        Self::new(|_ctx, row| Some(Cow::Borrowed(row)))
+    }
+
+    /// A map keeping the rows for which `predicate` returns `true`, as they are.
+    pub(crate) fn filtered(
+        predicate: impl Fn(&Ctx, &TRow) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self::new(move |ctx, row| predicate(ctx, row).then_some(Cow::Borrowed(row)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bindings::Player;
+    use std::sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{Receiver, channel},
+    };
+
+    // The tests give the event of the rows as the context of the callbacks
+    impl RowEventContext for StdbEventInfo {
+        fn event_info(&self) -> StdbEventInfo {
+            self.clone()
+        }
+    }
+
+    type RowCallback = Box<dyn FnMut(&StdbEventInfo, &Player) + Send>;
+    type UpdateCallback = Box<dyn FnMut(&StdbEventInfo, &Player, &Player) + Send>;
+
+    /// A table of players whose callbacks are called by the tests.
+    #[derive(Default)]
+    struct FakeTable {
+        insert: Mutex<Option<RowCallback>>,
+        delete: Mutex<Option<RowCallback>>,
+        update: Mutex<Option<UpdateCallback>>,
+    }
+
+    impl FakeTable {
+        fn insert(&self, row: &Player) {
+            if let Some(callback) = self.insert.lock().unwrap().as_mut() {
+                callback(&StdbEventInfo::UnknownTransaction, row);
+            }
+        }
+
+        fn delete(&self, row: &Player) {
+            if let Some(callback) = self.delete.lock().unwrap().as_mut() {
+                callback(&StdbEventInfo::UnknownTransaction, row);
+            }
+        }
+
+        fn update(&self, old: &Player, new: &Player) {
+            if let Some(callback) = self.update.lock().unwrap().as_mut() {
+                callback(&StdbEventInfo::UnknownTransaction, old, new);
+            }
+        }
+    }
+
+    impl TableAccessor<()> for FakeTable {
+        type Row = Player;
+        type EventContext = StdbEventInfo;
+        type InsertCallbackId = ();
+        type DeleteCallbackId = ();
+
+        fn on_insert(
+            &self,
+            _db: &(),
+            callback: impl FnMut(&StdbEventInfo, &Player) + Send + 'static,
+        ) {
+            *self.insert.lock().unwrap() = Some(Box::new(callback));
+        }
+
+        fn remove_on_insert(&self, _db: &(), _callback: ()) {
+            *self.insert.lock().unwrap() = None;
+        }
+
+        fn on_delete(
+            &self,
+            _db: &(),
+            callback: impl FnMut(&StdbEventInfo, &Player) + Send + 'static,
+        ) {
+            *self.delete.lock().unwrap() = Some(Box::new(callback));
+        }
+
+        fn remove_on_delete(&self, _db: &(), _callback: ()) {
+            *self.delete.lock().unwrap() = None;
+        }
+    }
+
+    impl TableWithPrimaryKeyAccessor<()> for FakeTable {
+        type UpdateCallbackId = ();
+
+        fn on_update(
+            &self,
+            _db: &(),
+            callback: impl FnMut(&StdbEventInfo, &Player, &Player) + Send + 'static,
+        ) {
+            *self.update.lock().unwrap() = Some(Box::new(callback));
+        }
+
+        fn remove_on_update(&self, _db: &(), _callback: ()) {
+            *self.update.lock().unwrap() = None;
+        }
+    }
+
+    struct Receivers<T> {
+        insert: Receiver<InsertMessage<T>>,
+        update: Receiver<UpdateMessage<T>>,
+        delete: Receiver<DeleteMessage<T>>,
+    }
+
+    impl<T> Receivers<T> {
+        fn assert_empty(&self) {
+            assert!(self.insert.try_recv().is_err());
+            assert!(self.update.try_recv().is_err());
+            assert!(self.delete.try_recv().is_err());
+        }
+    }
+
+    fn senders<T>() -> (TableSenders<T>, Receivers<T>) {
+        let (insert, insert_receiver) = channel();
+        let (update, update_receiver) = channel();
+        let (delete, delete_receiver) = channel();
+        let senders = TableSenders {
+            insert: Some(insert),
+            update: Some(update),
+            delete: Some(delete),
+            ..Default::default()
+        };
+        let receivers = Receivers {
+            insert: insert_receiver,
+            update: update_receiver,
+            delete: delete_receiver,
+        };
+        (senders, receivers)
+    }
+
+    /// Forwards the callbacks of a fake table to senders, through `map`.
+    fn forward<T: Send + Sync + Clone + 'static>(
+        map: RowMap<StdbEventInfo, Player, T>,
+    ) -> (FakeTable, Receivers<T>) {
+        let (table, (senders, receivers)) = (FakeTable::default(), senders());
+        let _ = senders.clone().on_update(&table, &(), map.clone());
+        let _ = senders.on_insert_delete(&table, &(), map);
+        (table, receivers)
+    }
+
+    fn player(id: u64, name: &str) -> Player {
+        Player {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    fn in_lobby() -> RowMap<StdbEventInfo, Player, Player> {
+        RowMap::filtered(|_ctx, player: &Player| player.name.starts_with("lobby"))
+    }
+
+    #[test]
+    fn row_map_all_borrows_every_row() {
+        let row = player(1, "Alice");
+        let map = RowMap::<(), Player, Player>::all();
+        assert!(matches!(map.map(&(), &row), Some(Cow::Borrowed(kept)) if *kept == row));
+    }
+
+    #[test]
+    fn row_map_filtered_leaves_out_rows() {
+        let map = in_lobby();
+        let (kept, left_out) = (player(1, "lobby Alice"), player(2, "Bob"));
+        let event = StdbEventInfo::UnknownTransaction;
+        assert!(matches!(map.map(&event, &kept), Some(Cow::Borrowed(row)) if *row == kept));
+        assert!(map.map(&event, &left_out).is_none());
+    }
+
+    #[test]
+    fn filtered_out_rows_send_nothing() {
+        let (table, receivers) = forward(in_lobby());
+        table.insert(&player(1, "Alice"));
+        table.update(&player(1, "Alice"), &player(1, "Bob"));
+        table.delete(&player(1, "Bob"));
+        receivers.assert_empty();
+    }
+
+    #[test]
+    fn kept_rows_are_sent() {
+        let (table, receivers) = forward(in_lobby());
+        table.insert(&player(1, "lobby Alice"));
+        table.update(&player(1, "lobby Alice"), &player(1, "lobby Bob"));
+        table.delete(&player(1, "lobby Bob"));
+
+        let insert = receivers.insert.try_recv().unwrap();
+        assert_eq!(insert.row, player(1, "lobby Alice"));
+        assert_eq!(insert.event, StdbEventInfo::UnknownTransaction);
+        let update = receivers.update.try_recv().unwrap();
+        assert_eq!(
+            (update.old, update.new),
+            (player(1, "lobby Alice"), player(1, "lobby Bob"))
+        );
+        assert_eq!(
+            receivers.delete.try_recv().unwrap().row,
+            player(1, "lobby Bob")
+        );
+        receivers.assert_empty();
+    }
+
+    #[test]
+    fn updates_crossing_the_filter_are_sent_as_insertions_or_deletions() {
+        let (table, receivers) = forward(in_lobby());
+        table.update(&player(1, "lobby Alice"), &player(1, "Alice"));
+        assert_eq!(
+            receivers.delete.try_recv().unwrap().row,
+            player(1, "lobby Alice")
+        );
+        receivers.assert_empty();
+
+        table.update(&player(1, "Alice"), &player(1, "lobby Alice"));
+        assert_eq!(
+            receivers.insert.try_recv().unwrap().row,
+            player(1, "lobby Alice")
+        );
+        receivers.assert_empty();
+    }
+
+    #[test]
+    fn send_update_follows_the_kept_rows() {
+        let (senders, receivers) = senders::<Player>();
+        let event = StdbEventInfo::UnknownTransaction;
+        let (old, new) = (player(1, "Alice"), player(1, "Bob"));
+
+        senders.send_update(&event, None, None);
+        receivers.assert_empty();
+
+        senders.send_update(&event, Some(Cow::Borrowed(&old)), None);
+        assert_eq!(receivers.delete.try_recv().unwrap().row, old);
+        senders.send_update(&event, None, Some(Cow::Borrowed(&new)));
+        assert_eq!(receivers.insert.try_recv().unwrap().row, new);
+        senders.send_update(&event, Some(Cow::Borrowed(&old)), Some(Cow::Borrowed(&new)));
+        let update = receivers.update.try_recv().unwrap();
+        assert_eq!((update.old, update.new), (old, new));
+        receivers.assert_empty();
+    }
+
+    #[test]
+    fn row_map_projected_owns_the_projections() {
+        let map = RowMap::<(), Player, String>::projected(|player| player.name.clone());
+        let row = player(1, "Alice");
+        let projection = map.map(&(), &row);
+        assert!(matches!(projection, Some(Cow::Owned(name)) if name == "Alice"));
+    }
+
+    #[test]
+    fn mapped_rows_are_sent_projected() {
+        let (table, receivers) = forward(RowMap::projected(|player: &Player| player.name.clone()));
+        table.insert(&player(1, "Alice"));
+        table.update(&player(1, "Alice"), &player(1, "Bob"));
+        table.delete(&player(1, "Bob"));
+
+        assert_eq!(receivers.insert.try_recv().unwrap().row, "Alice");
+        let update = receivers.update.try_recv().unwrap();
+        assert_eq!((update.old.as_str(), update.new.as_str()), ("Alice", "Bob"));
+        assert_eq!(receivers.delete.try_recv().unwrap().row, "Bob");
+        receivers.assert_empty();
+    }
+
+    /// Counts its clones.
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Self(Arc::clone(&self.0))
+        }
+    }
+
+    #[test]
+    fn shared_rows_are_cloned_for_all_messages_but_the_last() {
+        let clones = Arc::new(AtomicUsize::new(0));
+        let row = Counted(clones.clone());
+        let mut owned = SharedRow::new(Cow::<Counted>::Owned(row), [true, false, true]);
+        owned.take();
+        owned.take();
+        assert_eq!(clones.load(Ordering::Relaxed), 1);
+
+        let row = Counted(clones.clone());
+        let mut borrowed = SharedRow::new(Cow::Borrowed(&row), [true, true]);
+        borrowed.take();
+        borrowed.take();
+        assert_eq!(clones.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn unregistering_removes_the_callbacks() {
+        let (table, (senders, receivers)) = (FakeTable::default(), senders());
+        let unregister_update = senders.clone().on_update(&table, &(), RowMap::all());
+        let unregister_insert_delete = senders.on_insert_delete(&table, &(), RowMap::all());
+        unregister_update(&table, &());
+        unregister_insert_delete(&table, &());
+
+        table.insert(&player(1, "Alice"));
+        table.update(&player(1, "Alice"), &player(1, "Bob"));
+        table.delete(&player(1, "Bob"));
+        receivers.assert_empty();
     }
 }
@@ -0,0 +1,136 @@
+use std::{fs, io, path::PathBuf, sync::Arc};
+
+use bevy::log::warn;
+use spacetimedb_sdk::Error;
+use tungstenite::http::StatusCode;
+
+/// Persists the access tokens received by [`crate::StdbPlugin`], to re-authenticate as the same `Identity`.
+///
+/// Set it with [`crate::StdbPlugin::with_token_store`]: when connecting without a token, the one stored for
+/// the module is used, and the token received once connected is saved. Tokens are stored per module name.
+pub trait TokenStore: Send + Sync + 'static {
+    /// Returns the token stored for the given module, if any.
+    fn load(&self, module_name: &str) -> Option<String>;
+
+    /// Stores the token of the given module, replacing the previous one.
+    fn save(&self, module_name: &str, token: &str);
+
+    /// Removes the token stored for the given module.
+    fn clear(&self, module_name: &str);
+}
+
+/// A [`TokenStore`] keeping each token in a `<module name>.token` file of a directory.
+///
+/// ```ignore
+/// StdbPlugin::default().with_token_store(FileTokenStore::new(".spacetime_tokens"))
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    dir: PathBuf,
+}
+
+impl FileTokenStore {
+    /// Creates a store in the given directory, created on the first save.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The file holding the token of the given module.
+    pub fn path(&self, module_name: &str) -> PathBuf {
+        // Module names may be identities or contain characters that are not valid in a file name
+        let file_name: String = module_name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{file_name}.token"))
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self, module_name: &str) -> Option<String> {
+        let token = fs::read_to_string(self.path(module_name)).ok()?;
+        let token = token.trim();
+        (!token.is_empty()).then(|| token.to_string())
+    }
+
+    fn save(&self, module_name: &str, token: &str) {
+        let result =
+            fs::create_dir_all(&self.dir).and_then(|_| fs::write(self.path(module_name), token));
+        if let Err(err) = result {
+            warn!("Failed to save the SpacetimeDB token of {module_name}: {err}");
+        }
+    }
+
+    fn clear(&self, module_name: &str) {
+        match fs::remove_file(self.path(module_name)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                warn!("Failed to clear the SpacetimeDB token of {module_name}: {err}");
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether the host refused the connection because of its token.
+fn is_auth_failure(err: &Error) -> bool {
+    let mut next: Option<&dyn std::error::Error> = Some(err);
+    while let Some(err) = next {
+        if err.source().is_some_and(is_rejected_upgrade) {
+            return true;
+        }
+        // The internal errors of the SDK only expose their cause through the deprecated method
+        #[allow(deprecated)]
+        let cause = err.cause();
+        next = err
+            .source()
+            .map(|source| source as &dyn std::error::Error)
+            .or(cause);
+    }
+    false
+}
+
+/// Whether the error is the WebSocket upgrade being rejected with 401 Unauthorized or 403 Forbidden.
+fn is_rejected_upgrade(err: &(dyn std::error::Error + 'static)) -> bool {
+    let err = err
+        .downcast_ref::<Arc<tungstenite::Error>>()
+        .map(AsRef::as_ref)
+        .or_else(|| err.downcast_ref::<tungstenite::Error>());
+    matches!(
+        err,
+        Some(tungstenite::Error::Http(response))
+            if matches!(response.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+    )
+}
+
+/// The token of a connection in the [`TokenStore`] of the plugin.
+#[derive(Clone)]
+pub(crate) struct StoredToken {
+    pub(crate) store: Arc<dyn TokenStore>,
+    pub(crate) module_name: String,
+    pub(crate) clear_on_auth_failure: bool,
+}
+
+impl StoredToken {
+    /// Returns the token stored for the module.
+    pub(crate) fn load(&self) -> Option<String> {
+        self.store.load(&self.module_name)
+    }
+
+    /// Stores the token received once connected.
+    pub(crate) fn save(&self, token: &str) {
+        self.store.save(&self.module_name, token);
+    }
+
+    /// Clears the stored token if the connection failed because the host rejected it.
+    pub(crate) fn on_connect_error(&self, err: &Error) {
+        if self.clear_on_auth_failure && is_auth_failure(err) {
+            self.store.clear(&self.module_name);
+        }
+    }
+}
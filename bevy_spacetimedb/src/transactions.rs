@@ -0,0 +1,445 @@
+use std::{
+    any::Any,
+    fmt,
+    marker::PhantomData,
+    sync::{Arc, Mutex, mpsc::Sender},
+};
+
+use bevy::{
+    app::PreUpdate,
+    ecs::{schedule::Schedules, world::World},
+    prelude::{IntoScheduleConfigs, Message, Res, Resource},
+};
+use spacetimedb_sdk::{
+    __codegen::{self as spacetime_codegen, AbstractEventContext},
+    Event, ReducerEvent,
+};
+
+use crate::{
+    StdbEventInfo, StdbPlugin, TableAccessor, TableWithPrimaryKeyAccessor,
+    channel_receiver::channel_to_message, tables::RowMap,
+};
+
+/// A boxed row of a [`TransactionMessage`], its tables having rows of different types.
+type BoxedRow = Box<dyn Any + Send + Sync>;
+
+/// A row change of a [`TransactionMessage`].
+enum TransactionChange {
+    Insert(BoxedRow),
+    Update(BoxedRow, BoxedRow),
+    Delete(BoxedRow),
+}
+
+/// A message grouping the row changes of all the tables from the server update of one reducer call, emitted by
+/// the plugin with the label `L` when enabled with [`StdbPlugin::with_transactions`].
+///
+/// `R` is the `Reducer` enum of the module bindings. The changes of each table are read by row type,
+/// in the order they were applied, so the changes made to several tables by a reducer can be applied together.
+///
+/// ```ignore
+/// fn apply_trades(mut transactions: ReadTransactionMessage<Reducer>) {
+///     for transaction in transactions.read() {
+///         let removed = transaction.deletes::<InventoryItem>();
+///         let added = transaction.inserts::<InventoryItem>();
+///         let players = transaction.updates::<Player>();
+///     }
+/// }
+/// ```
+///
+/// The row messages of the tables are still emitted. Only the updates caused by a reducer are grouped: the rows
+/// of subscription changes, transactions of unknown reducers and, when the light mode is used, reducers called
+/// by other clients, have no reducer event to tell their update apart and are only sent as row messages.
+///
+/// The SDK doesn't tell when an update is fully applied, so a transaction is emitted once rows of another
+/// update arrive, or on the first frame during which none of its rows arrived. A transaction always holds
+/// rows of a single reducer call, and all of them unless the thread applying the update was paused for
+/// a whole frame in the middle of it, in which case the update is split in several transactions carrying
+/// the same reducer event.
+#[derive(Message)]
+pub struct TransactionMessage<R, L = ()> {
+    /// The reducer event that caused the update, always set by the plugin. It is `None` for transactions created
+    /// with [`TransactionMessage::new`] in tests, as a `ReducerEvent` can only be created by the SDK.
+    pub reducer_event: Option<ReducerEvent<R>>,
+    changes: Vec<TransactionChange>,
+    label: PhantomData<fn() -> L>,
+}
+
+impl<R, L> TransactionMessage<R, L> {
+    /// Creates an empty transaction, to be filled with the `with_*` methods, see [`crate::injection`].
+    pub fn new(reducer_event: Option<ReducerEvent<R>>) -> Self {
+        Self {
+            reducer_event,
+            changes: Vec::new(),
+            label: PhantomData,
+        }
+    }
+
+    /// Adds the insertion of a row.
+    pub fn with_insert<T: Send + Sync + 'static>(mut self, row: T) -> Self {
+        self.changes.push(TransactionChange::Insert(Box::new(row)));
+        self
+    }
+
+    /// Adds the update of a row.
+    pub fn with_update<T: Send + Sync + 'static>(mut self, old: T, new: T) -> Self {
+        self.changes
+            .push(TransactionChange::Update(Box::new(old), Box::new(new)));
+        self
+    }
+
+    /// Adds the deletion of a row.
+    pub fn with_delete<T: Send + Sync + 'static>(mut self, row: T) -> Self {
+        self.changes.push(TransactionChange::Delete(Box::new(row)));
+        self
+    }
+
+    /// The rows of type `T` inserted by the update.
+    pub fn inserts<T: 'static>(&self) -> impl Iterator<Item = &T> {
+        self.changes.iter().filter_map(|change| match change {
+            TransactionChange::Insert(row) => row.downcast_ref(),
+            _ => None,
+        })
+    }
+
+    /// The old and new rows of type `T` updated by the update.
+    pub fn updates<T: 'static>(&self) -> impl Iterator<Item = (&T, &T)> {
+        self.changes.iter().filter_map(|change| match change {
+            TransactionChange::Update(old, new) => old.downcast_ref().zip(new.downcast_ref()),
+            _ => None,
+        })
+    }
+
+    /// The rows of type `T` deleted by the update.
+    pub fn deletes<T: 'static>(&self) -> impl Iterator<Item = &T> {
+        self.changes.iter().filter_map(|change| match change {
+            TransactionChange::Delete(row) => row.downcast_ref(),
+            _ => None,
+        })
+    }
+
+    /// Number of row changes, of all tables.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Whether the update changed no rows.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl<R: fmt::Debug, L> fmt::Debug for TransactionMessage<R, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransactionMessage")
+            .field("reducer_event", &self.reducer_event)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<
+    C: spacetime_codegen::DbConnection<Module = M> + spacetimedb_sdk::DbContext,
+    M: spacetime_codegen::SpacetimeModule<DbConnection = C>,
+    L: Send + Sync + 'static,
+> StdbPlugin<C, M, L>
+where
+    M::Reducer: Sync,
+{
+    /// Also emits a [`TransactionMessage`] grouping the row changes of all the tables registered on the plugin
+    /// from the server update of each reducer call, along with its reducer event.
+    pub fn with_transactions(self) -> Self {
+        // Registered when the plugin is built, before connecting, so the table callbacks of every connection find it
+        let register_messages = |plugin: &Self, world: &mut World| {
+            if world.contains_resource::<Transactions<M, L>>() {
+                return;
+            }
+            let send = plugin.message_sender::<TransactionMessage<M::Reducer, L>>(world);
+            world.insert_resource(Transactions::<M, L>::new(send));
+            world.resource_mut::<Schedules>().add_systems(
+                PreUpdate,
+                flush_transactions::<M, L>
+                    .before(channel_to_message::<TransactionMessage<M::Reducer, L>>),
+            );
+        };
+        self.message_registers
+            .lock()
+            .unwrap()
+            .push(Box::new(register_messages));
+        self
+    }
+}
+
+/// The transaction whose rows are being received.
+struct OpenTransaction<R, L> {
+    /// Identifies the reducer call of the rows: row callbacks don't tell which update they belong to,
+    /// but all the rows of an update come with the same reducer event.
+    key: StdbEventInfo,
+    transaction: TransactionMessage<R, L>,
+    /// The number of changes when the transaction was last checked by [`flush_transactions`].
+    checked_len: usize,
+}
+
+/// Groups the row changes of the tables registered on the plugin with the label `L` into transactions.
+///
+/// The table callbacks add the rows of reducer events to the open transaction, which is sent once rows of
+/// another update arrive, or when no rows were added to it since the previous frame.
+#[derive(Resource)]
+pub(crate) struct Transactions<M: spacetime_codegen::SpacetimeModule, L> {
+    #[allow(clippy::type_complexity)]
+    open: Arc<Mutex<Option<OpenTransaction<M::Reducer, L>>>>,
+    send: Sender<TransactionMessage<M::Reducer, L>>,
+}
+
+impl<M: spacetime_codegen::SpacetimeModule, L> Clone for Transactions<M, L> {
+    fn clone(&self) -> Self {
+        Self {
+            open: Arc::clone(&self.open),
+            send: self.send.clone(),
+        }
+    }
+}
+
+impl<M: spacetime_codegen::SpacetimeModule, L: 'static> Transactions<M, L> {
+    pub(crate) fn new(send: Sender<TransactionMessage<M::Reducer, L>>) -> Self {
+        Self {
+            open: Arc::default(),
+            send,
+        }
+    }
+
+    /// Adds a row change to the transaction of the reducer call `key`, sending the open transaction if it is
+    /// another one. The reducer event is cloned into the transaction when it is opened.
+    fn push(
+        &self,
+        key: StdbEventInfo,
+        reducer_event: Option<&ReducerEvent<M::Reducer>>,
+        change: TransactionChange,
+    ) {
+        let mut open = self.open.lock().unwrap();
+        if open.as_ref().is_some_and(|open| open.key != key) {
+            let _ = self.send.send(open.take().unwrap().transaction);
+        }
+        let open = open.get_or_insert_with(|| OpenTransaction {
+            key,
+            transaction: TransactionMessage::new(reducer_event.cloned()),
+            checked_len: 0,
+        });
+        open.transaction.changes.push(change);
+    }
+
+    /// Returns the reducer event of a row change, or sends the open transaction if the row has none,
+    /// as it comes from another update.
+    fn reducer_event<'e>(
+        &self,
+        event: &'e Event<M::Reducer>,
+    ) -> Option<&'e ReducerEvent<M::Reducer>> {
+        match event {
+            Event::Reducer(event) => Some(event),
+            _ => {
+                if let Some(open) = self.open.lock().unwrap().take() {
+                    let _ = self.send.send(open.transaction);
+                }
+                None
+            }
+        }
+    }
+
+    /// Sends the open transaction if no rows were added to it since the last call.
+    fn flush_idle(&self) {
+        let mut open = self.open.lock().unwrap();
+        match open.as_mut() {
+            Some(transaction) if transaction.checked_len == transaction.transaction.len() => {
+                let _ = self.send.send(open.take().unwrap().transaction);
+            }
+            Some(transaction) => transaction.checked_len = transaction.transaction.len(),
+            None => {}
+        }
+    }
+
+    /// Adds the rows returned by `map` for the `on_insert` and `on_delete` callbacks of the table
+    /// to the transactions, returns a closure removing them.
+    pub(crate) fn on_insert_delete<TRow, V, A>(
+        &self,
+        accessor: &A,
+        db: &V,
+        map: RowMap<M::EventContext, A::Row, TRow>,
+    ) -> impl FnOnce(&A, &V) + Send + use<TRow, V, A, M, L>
+    where
+        TRow: Send + Sync + Clone + 'static,
+        A: TableAccessor<V, EventContext = M::EventContext>,
+    {
+        let (transactions, insert_map) = (self.clone(), map.clone());
+        let insert = accessor.on_insert(db, move |ctx, row| {
+            let Some(event) = transactions.reducer_event(ctx.event()) else {
+                return;
+            };
+            if let Some(row) = insert_map.map(ctx, row) {
+                transactions.push(
+                    StdbEventInfo::new::<M>(ctx.event()),
+                    Some(event),
+                    TransactionChange::Insert(Box::new(row.into_owned())),
+                );
+            }
+        });
+        let transactions = self.clone();
+        let delete = accessor.on_delete(db, move |ctx, row| {
+            let Some(event) = transactions.reducer_event(ctx.event()) else {
+                return;
+            };
+            if let Some(row) = map.map(ctx, row) {
+                transactions.push(
+                    StdbEventInfo::new::<M>(ctx.event()),
+                    Some(event),
+                    TransactionChange::Delete(Box::new(row.into_owned())),
+                );
+            }
+        });
+        move |accessor, db| {
+            accessor.remove_on_insert(db, insert);
+            accessor.remove_on_delete(db, delete);
+        }
+    }
+
+    /// Adds the rows returned by `map` for the `on_update` callback of the table to the transactions,
+    /// returns a closure removing it.
+    pub(crate) fn on_update<TRow, V, A>(
+        &self,
+        accessor: &A,
+        db: &V,
+        map: RowMap<M::EventContext, A::Row, TRow>,
+    ) -> impl FnOnce(&A, &V) + Send + use<TRow, V, A, M, L>
+    where
+        TRow: Send + Sync + Clone + 'static,
+        A: TableWithPrimaryKeyAccessor<V, EventContext = M::EventContext>,
+    {
+        let transactions = self.clone();
+        let update = accessor.on_update(db, move |ctx, old, new| {
+            let Some(event) = transactions.reducer_event(ctx.event()) else {
+                return;
+            };
+            let change = match (map.map(ctx, old), map.map(ctx, new)) {
+                (Some(old), Some(new)) => TransactionChange::Update(
+                    Box::new(old.into_owned()),
+                    Box::new(new.into_owned()),
+                ),
+                (Some(old), None) => TransactionChange::Delete(Box::new(old.into_owned())),
+                (None, Some(new)) => TransactionChange::Insert(Box::new(new.into_owned())),
+                (None, None) => return,
+            };
+            transactions.push(StdbEventInfo::new::<M>(ctx.event()), Some(event), change);
+        });
+        move |accessor, db| accessor.remove_on_update(db, update)
+    }
+}
+
+/// Sends the transaction whose rows stopped arriving.
+fn flush_transactions<M: spacetime_codegen::SpacetimeModule, L: Send + Sync + 'static>(
+    transactions: Res<Transactions<M, L>>,
+) where
+    M::Reducer: Sync,
+{
+    transactions.flush_idle();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bindings::{Player, Reducer, RemoteModule};
+    use spacetimedb_sdk::{ConnectionId, Identity, Timestamp};
+    use std::sync::mpsc::{Receiver, channel};
+
+    fn transactions() -> (
+        Transactions<RemoteModule, ()>,
+        Receiver<TransactionMessage<Reducer>>,
+    ) {
+        let (send, receive) = channel();
+        (Transactions::new(send), receive)
+    }
+
+    fn reducer_call(micros: i64) -> StdbEventInfo {
+        reducer_call_by(micros, Some(ConnectionId::ZERO))
+    }
+
+    fn reducer_call_by(micros: i64, caller_connection_id: Option<ConnectionId>) -> StdbEventInfo {
+        StdbEventInfo::Reducer {
+            reducer: "set_name",
+            caller_identity: Identity::ZERO,
+            caller_connection_id,
+            timestamp: Timestamp::from_micros_since_unix_epoch(micros),
+        }
+    }
+
+    fn insert(id: u64) -> TransactionChange {
+        TransactionChange::Insert(Box::new(Player {
+            id,
+            name: "Alice".to_string(),
+        }))
+    }
+
+    fn ids(transaction: &TransactionMessage<Reducer>) -> Vec<u64> {
+        transaction
+            .inserts::<Player>()
+            .map(|player| player.id)
+            .collect()
+    }
+
+    #[test]
+    fn rows_of_a_reducer_call_are_grouped() {
+        let (transactions, receive) = transactions();
+        transactions.push(reducer_call(1), None, insert(1));
+        transactions.push(reducer_call(1), None, insert(2));
+        assert!(receive.try_recv().is_err());
+
+        transactions.flush_idle();
+        transactions.flush_idle();
+        assert_eq!(ids(&receive.try_recv().unwrap()), vec![1, 2]);
+        assert!(receive.try_recv().is_err());
+    }
+
+    #[test]
+    fn rows_of_another_reducer_call_send_the_open_transaction() {
+        let (transactions, receive) = transactions();
+        transactions.push(reducer_call(1), None, insert(1));
+        transactions.push(reducer_call(2), None, insert(2));
+        assert_eq!(ids(&receive.try_recv().unwrap()), vec![1]);
+        assert!(receive.try_recv().is_err());
+
+        transactions.push(reducer_call_by(2, None), None, insert(3));
+        assert_eq!(ids(&receive.try_recv().unwrap()), vec![2]);
+    }
+
+    #[test]
+    fn rows_without_reducer_event_send_the_open_transaction() {
+        let (transactions, receive) = transactions();
+        transactions.push(reducer_call(1), None, insert(1));
+        assert!(
+            transactions
+                .reducer_event(&Event::SubscribeApplied)
+                .is_none()
+        );
+        assert_eq!(ids(&receive.try_recv().unwrap()), vec![1]);
+
+        assert!(
+            transactions
+                .reducer_event(&Event::UnknownTransaction)
+                .is_none()
+        );
+        assert!(receive.try_recv().is_err());
+    }
+
+    #[test]
+    fn flush_idle_waits_for_rows_to_stop_arriving() {
+        let (transactions, receive) = transactions();
+        transactions.flush_idle();
+        assert!(receive.try_recv().is_err());
+
+        transactions.push(reducer_call(1), None, insert(1));
+        transactions.flush_idle();
+        transactions.push(reducer_call(1), None, insert(2));
+        transactions.flush_idle();
+        assert!(receive.try_recv().is_err());
+
+        transactions.flush_idle();
+        assert_eq!(ids(&receive.try_recv().unwrap()), vec![1, 2]);
+    }
+}
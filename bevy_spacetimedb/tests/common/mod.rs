@@ -0,0 +1,434 @@
+//! A minimal, hand-written stand-in for the bindings generated by `spacetime generate`,
+//! describing a module with a `player` table, a `set_name` reducer and an `echo` procedure.
+//!
+//! It is only meant to let tests build a `StdbPlugin` without a SpacetimeDB host:
+//! connecting fails or is never identified, so the parts only reached by a live connection are left unimplemented.
+//!
+//! It follows the codegen traits of spacetimedb-sdk 1.11, the minor version pinned in the workspace manifest.
+//! Update it along with that version.
+#![allow(dead_code)]
+
+use spacetimedb_sdk::__codegen::{self as __sdk, __lib, __ws};
+use std::marker::PhantomData;
+use std::net::TcpListener;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+
+/// The URI used by tests, nothing is listening on it so connecting fails right away.
+pub const UNREACHABLE_URI: &str = "http://127.0.0.1:1";
+
+/// Starts a host accepting WebSocket connections without ever answering them, and returns its URI.
+///
+/// Connections to it are opened, but never identified, as if the host hadn't sent their identity yet.
+pub fn silent_host() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let uri = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                return;
+            };
+            std::thread::spawn(move || {
+                if let Ok(mut socket) = tungstenite::accept_hdr(stream, accept_protocol) {
+                    // Keep the connection open until the client closes it
+                    while socket.read().is_ok() {}
+                }
+            });
+        }
+    });
+    uri
+}
+
+/// Accepts the protocol requested by the client, which it requires in the response.
+#[allow(clippy::result_large_err)] // The signature of the handshake callbacks of tungstenite
+fn accept_protocol(request: &Request, mut response: Response) -> Result<Response, ErrorResponse> {
+    if let Some(protocol) = request.headers().get("Sec-WebSocket-Protocol") {
+        response
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", protocol.clone());
+    }
+    Ok(response)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Player {
+    pub id: u64,
+    pub name: String,
+}
+
+impl __sdk::InModule for Player {
+    type Module = RemoteModule;
+}
+
+#[derive(Clone, Debug)]
+pub enum Reducer {
+    SetName { name: String },
+}
+
+impl __sdk::InModule for Reducer {
+    type Module = RemoteModule;
+}
+
+impl __sdk::Reducer for Reducer {
+    fn reducer_name(&self) -> &'static str {
+        match self {
+            Reducer::SetName { .. } => "set_name",
+        }
+    }
+}
+
+impl TryFrom<__ws::ReducerCallInfo<__ws::BsatnFormat>> for Reducer {
+    type Error = __sdk::Error;
+
+    fn try_from(value: __ws::ReducerCallInfo<__ws::BsatnFormat>) -> __sdk::Result<Self> {
+        Err(
+            __sdk::InternalError::unknown_name("reducer", &value.reducer_name, "ReducerCallInfo")
+                .into(),
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct DbUpdate;
+
+impl __sdk::InModule for DbUpdate {
+    type Module = RemoteModule;
+}
+
+impl TryFrom<__ws::DatabaseUpdate<__ws::BsatnFormat>> for DbUpdate {
+    type Error = __sdk::Error;
+
+    fn try_from(_raw: __ws::DatabaseUpdate<__ws::BsatnFormat>) -> __sdk::Result<Self> {
+        Ok(DbUpdate)
+    }
+}
+
+impl __sdk::DbUpdate for DbUpdate {
+    fn apply_to_client_cache(
+        &self,
+        _cache: &mut __sdk::ClientCache<RemoteModule>,
+    ) -> AppliedDiff<'_> {
+        AppliedDiff(PhantomData)
+    }
+}
+
+pub struct AppliedDiff<'r>(PhantomData<&'r ()>);
+
+impl __sdk::InModule for AppliedDiff<'_> {
+    type Module = RemoteModule;
+}
+
+impl<'r> __sdk::AppliedDiff<'r> for AppliedDiff<'r> {
+    fn invoke_row_callbacks(
+        &self,
+        _event: &EventContext,
+        _callbacks: &mut __sdk::DbCallbacks<RemoteModule>,
+    ) {
+    }
+}
+
+pub struct RemoteModule;
+
+impl __sdk::SpacetimeModule for RemoteModule {
+    type DbConnection = DbConnection;
+    type EventContext = EventContext;
+    type ReducerEventContext = ReducerEventContext;
+    type ProcedureEventContext = ProcedureEventContext;
+    type SubscriptionEventContext = SubscriptionEventContext;
+    type ErrorContext = ErrorContext;
+    type Reducer = Reducer;
+    type DbView = RemoteTables;
+    type Reducers = RemoteReducers;
+    type SetReducerFlags = SetReducerFlags;
+    type DbUpdate = DbUpdate;
+    type AppliedDiff<'r> = AppliedDiff<'r>;
+    type SubscriptionHandle = SubscriptionHandle;
+
+    fn register_tables(client_cache: &mut __sdk::ClientCache<Self>) {
+        client_cache.get_or_make_table::<Player>("player");
+    }
+}
+
+pub struct RemoteTables {
+    imp: __sdk::DbContextImpl<RemoteModule>,
+}
+
+impl __sdk::InModule for RemoteTables {
+    type Module = RemoteModule;
+}
+
+impl RemoteTables {
+    pub fn player(&self) -> PlayerTableHandle<'_> {
+        PlayerTableHandle {
+            imp: self.imp.get_table::<Player>("player"),
+            ctx: PhantomData,
+        }
+    }
+}
+
+pub struct PlayerTableHandle<'ctx> {
+    imp: __sdk::TableHandle<Player>,
+    ctx: PhantomData<&'ctx ()>,
+}
+
+impl __sdk::Table for PlayerTableHandle<'_> {
+    type Row = Player;
+    type EventContext = EventContext;
+
+    fn count(&self) -> u64 {
+        self.imp.count()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = Player> + '_ {
+        self.imp.iter()
+    }
+
+    type InsertCallbackId = __sdk::CallbackId;
+
+    fn on_insert(
+        &self,
+        callback: impl FnMut(&EventContext, &Player) + Send + 'static,
+    ) -> __sdk::CallbackId {
+        self.imp.on_insert(callback)
+    }
+
+    fn remove_on_insert(&self, callback: __sdk::CallbackId) {
+        self.imp.remove_on_insert(callback)
+    }
+
+    type DeleteCallbackId = __sdk::CallbackId;
+
+    fn on_delete(
+        &self,
+        callback: impl FnMut(&EventContext, &Player) + Send + 'static,
+    ) -> __sdk::CallbackId {
+        self.imp.on_delete(callback)
+    }
+
+    fn remove_on_delete(&self, callback: __sdk::CallbackId) {
+        self.imp.remove_on_delete(callback)
+    }
+}
+
+impl __sdk::TableWithPrimaryKey for PlayerTableHandle<'_> {
+    type UpdateCallbackId = __sdk::CallbackId;
+
+    fn on_update(
+        &self,
+        callback: impl FnMut(&EventContext, &Player, &Player) + Send + 'static,
+    ) -> __sdk::CallbackId {
+        self.imp.on_update(callback)
+    }
+
+    fn remove_on_update(&self, callback: __sdk::CallbackId) {
+        self.imp.remove_on_update(callback)
+    }
+}
+
+pub struct RemoteReducers {
+    imp: __sdk::DbContextImpl<RemoteModule>,
+}
+
+impl __sdk::InModule for RemoteReducers {
+    type Module = RemoteModule;
+}
+
+impl RemoteReducers {
+    pub fn on_set_name(
+        &self,
+        mut callback: impl FnMut(&ReducerEventContext, &String) + Send + 'static,
+    ) -> __sdk::CallbackId {
+        self.imp.on_reducer(
+            "set_name",
+            Box::new(move |ctx: &ReducerEventContext| {
+                let Reducer::SetName { name } = &ctx.event.reducer;
+                callback(ctx, name)
+            }),
+        )
+    }
+}
+
+pub struct RemoteProcedures {
+    imp: __sdk::DbContextImpl<RemoteModule>,
+}
+
+impl __sdk::InModule for RemoteProcedures {
+    type Module = RemoteModule;
+}
+
+#[derive(__lib::ser::Serialize)]
+#[sats(crate = __lib)]
+struct EchoArgs {
+    message: String,
+}
+
+impl __sdk::InModule for EchoArgs {
+    type Module = RemoteModule;
+}
+
+impl RemoteProcedures {
+    pub fn echo_then(
+        &self,
+        message: String,
+        callback: impl FnOnce(&ProcedureEventContext, Result<String, __sdk::InternalError>)
+        + Send
+        + 'static,
+    ) {
+        self.imp
+            .invoke_procedure_with_callback("echo", EchoArgs { message }, callback);
+    }
+}
+
+pub struct SetReducerFlags {
+    imp: __sdk::DbContextImpl<RemoteModule>,
+}
+
+impl __sdk::InModule for SetReducerFlags {
+    type Module = RemoteModule;
+}
+
+pub struct DbConnection {
+    pub db: RemoteTables,
+    pub reducers: RemoteReducers,
+    pub procedures: RemoteProcedures,
+    pub set_reducer_flags: SetReducerFlags,
+    imp: __sdk::DbContextImpl<RemoteModule>,
+}
+
+impl __sdk::InModule for DbConnection {
+    type Module = RemoteModule;
+}
+
+impl __sdk::DbConnection for DbConnection {
+    fn new(imp: __sdk::DbContextImpl<RemoteModule>) -> Self {
+        Self {
+            db: RemoteTables { imp: imp.clone() },
+            reducers: RemoteReducers { imp: imp.clone() },
+            procedures: RemoteProcedures { imp: imp.clone() },
+            set_reducer_flags: SetReducerFlags { imp: imp.clone() },
+            imp,
+        }
+    }
+}
+
+impl DbConnection {
+    pub fn run_threaded(&self) -> std::thread::JoinHandle<()> {
+        self.imp.run_threaded()
+    }
+}
+
+impl __sdk::DbContext for DbConnection {
+    type DbView = RemoteTables;
+    type Reducers = RemoteReducers;
+    type Procedures = RemoteProcedures;
+    type SetReducerFlags = SetReducerFlags;
+    type SubscriptionBuilder = __sdk::SubscriptionBuilder<RemoteModule>;
+
+    fn db(&self) -> &RemoteTables {
+        &self.db
+    }
+
+    fn reducers(&self) -> &RemoteReducers {
+        &self.reducers
+    }
+
+    fn procedures(&self) -> &RemoteProcedures {
+        &self.procedures
+    }
+
+    fn set_reducer_flags(&self) -> &SetReducerFlags {
+        &self.set_reducer_flags
+    }
+
+    fn is_active(&self) -> bool {
+        self.imp.is_active()
+    }
+
+    fn disconnect(&self) -> __sdk::Result<()> {
+        self.imp.disconnect()
+    }
+
+    fn subscription_builder(&self) -> Self::SubscriptionBuilder {
+        __sdk::SubscriptionBuilder::new(&self.imp)
+    }
+
+    fn try_identity(&self) -> Option<__sdk::Identity> {
+        self.imp.try_identity()
+    }
+
+    fn connection_id(&self) -> __sdk::ConnectionId {
+        self.imp.connection_id()
+    }
+
+    fn try_connection_id(&self) -> Option<__sdk::ConnectionId> {
+        self.imp.try_connection_id()
+    }
+}
+
+/// Declares an event context type, as the generated bindings do for each kind of callback.
+macro_rules! event_context {
+    ($name:ident, $event:ty, $kind:ident) => {
+        pub struct $name {
+            pub event: $event,
+            imp: __sdk::DbContextImpl<RemoteModule>,
+        }
+
+        impl __sdk::InModule for $name {
+            type Module = RemoteModule;
+        }
+
+        impl __sdk::AbstractEventContext for $name {
+            type Event = $event;
+
+            fn event(&self) -> &Self::Event {
+                &self.event
+            }
+
+            fn new(imp: __sdk::DbContextImpl<RemoteModule>, event: Self::Event) -> Self {
+                Self { event, imp }
+            }
+        }
+
+        impl __sdk::$kind for $name {}
+    };
+}
+
+event_context!(EventContext, __sdk::Event<Reducer>, EventContext);
+event_context!(
+    ReducerEventContext,
+    __sdk::ReducerEvent<Reducer>,
+    ReducerEventContext
+);
+event_context!(ProcedureEventContext, (), ProcedureEventContext);
+event_context!(SubscriptionEventContext, (), SubscriptionEventContext);
+event_context!(ErrorContext, Option<__sdk::Error>, ErrorContext);
+
+#[derive(Clone)]
+pub struct SubscriptionHandle {
+    imp: __sdk::SubscriptionHandleImpl<RemoteModule>,
+}
+
+impl __sdk::InModule for SubscriptionHandle {
+    type Module = RemoteModule;
+}
+
+impl __sdk::SubscriptionHandle for SubscriptionHandle {
+    fn new(imp: __sdk::SubscriptionHandleImpl<RemoteModule>) -> Self {
+        Self { imp }
+    }
+
+    fn is_ended(&self) -> bool {
+        self.imp.is_ended()
+    }
+
+    fn is_active(&self) -> bool {
+        self.imp.is_active()
+    }
+
+    fn unsubscribe_then(self, on_end: __sdk::OnEndedCallback<RemoteModule>) -> __sdk::Result<()> {
+        self.imp.unsubscribe_then(Some(on_end))
+    }
+
+    fn unsubscribe(self) -> __sdk::Result<()> {
+        self.imp.unsubscribe_then(None)
+    }
+}
@@ -0,0 +1,201 @@
+mod common;
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy_spacetimedb::{
+    InsertMessage, ReconnectPolicy, StdbCommandsExt, StdbConnectRequest, StdbConnection,
+    StdbConnectionErrorMessage, StdbConnectionState, StdbPlugin, StdbReconnectingMessage,
+    injection::StdbMessageInjectionExt,
+};
+use common::{DbConnection, Player, RemoteModule, RemoteTables, UNREACHABLE_URI, silent_host};
+use spacetimedb_sdk::Identity;
+
+fn plugin() -> StdbPlugin<DbConnection, RemoteModule> {
+    StdbPlugin::default()
+        .with_run_fn(DbConnection::run_threaded)
+        .with_delayed_connect(true)
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .add_plugins(plugin());
+    app
+}
+
+fn state(app: &App) -> StdbConnectionState {
+    *app.world().resource::<State<StdbConnectionState>>().get()
+}
+
+fn drain<T: Message>(app: &mut App) -> Vec<T> {
+    app.world_mut()
+        .resource_mut::<Messages<T>>()
+        .drain()
+        .collect()
+}
+
+#[test]
+fn a_delayed_connection_needs_no_uri_or_module_name() {
+    let mut app = app();
+    app.update();
+
+    assert_eq!(state(&app), StdbConnectionState::Disconnected);
+}
+
+#[test]
+#[should_panic(expected = "StdbPlugin needs the StatesPlugin")]
+fn the_connection_state_needs_the_states_plugin() {
+    App::new().add_plugins(MinimalPlugins).add_plugins(plugin());
+}
+
+#[test]
+fn connect_requests_open_a_connection_to_their_host() {
+    let mut app = app();
+    app.world_mut().write_message(
+        StdbConnectRequest::new()
+            .with_uri(UNREACHABLE_URI)
+            .with_module_name("test"),
+    );
+    app.update();
+    app.update();
+
+    // Nothing listens on the requested host, so the connection fails instead of panicking for a missing uri
+    assert_eq!(drain::<StdbConnectionErrorMessage>(&mut app).len(), 1);
+    assert!(
+        !app.world()
+            .contains_resource::<StdbConnection<DbConnection>>()
+    );
+}
+
+#[test]
+fn connections_are_opened_before_the_host_identifies_them() {
+    let mut app = app();
+    app.world_mut().write_message(
+        StdbConnectRequest::new()
+            .with_uri(silent_host())
+            .with_module_name("test"),
+    );
+    app.update();
+    app.update();
+
+    let connection = app.world().resource::<StdbConnection<DbConnection>>();
+    assert!(connection.try_connection_id().is_none());
+    assert_eq!(state(&app), StdbConnectionState::Connecting);
+}
+
+#[test]
+fn connect_requests_without_a_host_keep_the_current_connection() {
+    let mut app = app();
+    app.world_mut().write_message(
+        StdbConnectRequest::new()
+            .with_uri(silent_host())
+            .with_module_name("test"),
+    );
+    app.update();
+    app.update();
+
+    app.world_mut()
+        .write_message(StdbConnectRequest::new().with_module_name("test"));
+    app.update();
+    app.update();
+
+    let errors = drain::<StdbConnectionErrorMessage>(&mut app);
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors[0].err,
+        spacetimedb_sdk::Error::Disconnected
+    ));
+    assert!(
+        app.world()
+            .contains_resource::<StdbConnection<DbConnection>>()
+    );
+}
+
+#[test]
+fn commands_open_a_connection() {
+    let mut app = app();
+    app.world_mut()
+        .run_system_once(|mut commands: Commands| {
+            commands.stdb_connect_with::<DbConnection, RemoteModule>(
+                StdbConnectRequest::new()
+                    .with_uri(UNREACHABLE_URI)
+                    .with_module_name("test"),
+            );
+        })
+        .unwrap();
+    app.update();
+
+    assert_eq!(drain::<StdbConnectionErrorMessage>(&mut app).len(), 1);
+}
+
+#[test]
+fn commands_close_the_connection_without_reconnecting() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .add_plugins(plugin().with_reconnect(ReconnectPolicy::default()));
+    app.inject_connected(Identity::ZERO, "token");
+    app.update();
+    app.update();
+    assert_eq!(state(&app), StdbConnectionState::Connected);
+
+    app.world_mut()
+        .run_system_once(|mut commands: Commands| {
+            commands.stdb_disconnect::<DbConnection>();
+        })
+        .unwrap();
+    app.update();
+    app.update();
+
+    assert_eq!(state(&app), StdbConnectionState::Disconnected);
+    assert!(drain::<StdbReconnectingMessage>(&mut app).is_empty());
+}
+
+/// The label of a second connection to the same module.
+struct Match;
+
+#[test]
+fn labeled_plugins_connect_separately() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .add_plugins(plugin().add_table(RemoteTables::player))
+        .add_plugins(
+            StdbPlugin::<DbConnection, RemoteModule, Match>::default()
+                .with_run_fn(DbConnection::run_threaded)
+                .with_delayed_connect(true)
+                .add_table(RemoteTables::player),
+        );
+    app.world_mut().write_message(
+        StdbConnectRequest::<Match>::default()
+            .with_uri(UNREACHABLE_URI)
+            .with_module_name("match"),
+    );
+    app.update();
+    app.update();
+
+    assert_eq!(
+        drain::<StdbConnectionErrorMessage<Match>>(&mut app).len(),
+        1
+    );
+    assert!(drain::<StdbConnectionErrorMessage>(&mut app).is_empty());
+    assert_eq!(state(&app), StdbConnectionState::Disconnected);
+
+    // Both plugins share the messages of the table
+    app.inject_insert(Player {
+        id: 1,
+        name: "Alice".to_string(),
+    });
+    app.update();
+    assert_eq!(drain::<InsertMessage<Player>>(&mut app).len(), 1);
+}
+
+#[test]
+fn connect_requests_do_not_print_their_token() {
+    let request = StdbConnectRequest::new()
+        .with_module_name("test")
+        .with_token("secret-token");
+    let printed = format!("{request:?}");
+    assert!(!printed.contains("secret-token"));
+    assert!(printed.contains("<redacted>"));
+    assert!(format!("{:?}", StdbConnectRequest::new()).contains("token: None"));
+}
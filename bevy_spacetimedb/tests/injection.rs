@@ -0,0 +1,377 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy_spacetimedb::{
+    DeleteMessage, EntityRowInserted, InsertMessage, InsertUpdateMessage, ReconnectPolicy,
+    RowDeleted, RowInserted, StdbConnectionState, StdbEventInfo, StdbPlugin,
+    StdbReconnectingMessage, TableEntities, TableMessages, TransactionMessage, UpdateMessage,
+    injection::StdbMessageInjectionExt,
+};
+use common::{DbConnection, Player, Reducer, RemoteModule, RemoteTables, UNREACHABLE_URI};
+use spacetimedb_sdk::{Identity, Timestamp};
+use std::time::Duration;
+
+#[derive(Component, Clone, Debug, PartialEq)]
+struct PlayerName(String);
+
+impl From<Player> for PlayerName {
+    fn from(player: Player) -> Self {
+        PlayerName(player.name)
+    }
+}
+
+fn plugin() -> StdbPlugin<DbConnection, RemoteModule> {
+    StdbPlugin::default()
+        .with_uri(UNREACHABLE_URI)
+        .with_module_name("test")
+        .with_run_fn(DbConnection::run_threaded)
+        .with_delayed_connect(true)
+}
+
+fn app(plugin: StdbPlugin<DbConnection, RemoteModule>) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .add_plugins(plugin);
+    app
+}
+
+fn player(id: u64, name: &str) -> Player {
+    Player {
+        id,
+        name: name.to_string(),
+    }
+}
+
+fn drain<T: Message>(app: &mut App) -> Vec<T> {
+    app.world_mut()
+        .resource_mut::<Messages<T>>()
+        .drain()
+        .collect()
+}
+
+#[test]
+fn row_changes_are_read_as_messages() {
+    let mut app = app(plugin().add_table(RemoteTables::player));
+
+    app.inject_insert(player(1, "Alice"))
+        .inject_update(player(1, "Alice"), player(1, "Bob"))
+        .inject_delete(player(1, "Bob"));
+    app.update();
+
+    let inserted = drain::<InsertMessage<Player>>(&mut app);
+    assert_eq!(inserted.len(), 1);
+    assert_eq!(inserted[0].row, player(1, "Alice"));
+
+    let updated = drain::<UpdateMessage<Player>>(&mut app);
+    assert_eq!(updated.len(), 1);
+    assert_eq!(updated[0].old, player(1, "Alice"));
+    assert_eq!(updated[0].new, player(1, "Bob"));
+
+    let inserted_updated = drain::<InsertUpdateMessage<Player>>(&mut app);
+    assert_eq!(inserted_updated.len(), 2);
+    assert_eq!(inserted_updated[0].old, None);
+    assert_eq!(inserted_updated[1].old, Some(player(1, "Alice")));
+
+    let deleted = drain::<DeleteMessage<Player>>(&mut app);
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].row, player(1, "Bob"));
+}
+
+#[test]
+fn only_the_registered_table_messages_are_added() {
+    let mut app = app(plugin().add_partial_table(RemoteTables::player, TableMessages::no_update()));
+
+    app.inject_insert(player(1, "Alice"))
+        .inject_update(player(1, "Alice"), player(1, "Bob"));
+    app.update();
+
+    assert_eq!(drain::<InsertMessage<Player>>(&mut app).len(), 1);
+    assert!(
+        !app.world()
+            .contains_resource::<Messages<UpdateMessage<Player>>>()
+    );
+    assert!(
+        !app.world()
+            .contains_resource::<Messages<InsertUpdateMessage<Player>>>()
+    );
+}
+
+#[test]
+fn filtered_tables_register_their_messages() {
+    let mut app = app(plugin().add_table_filtered(
+        RemoteTables::player,
+        TableMessages::all(),
+        |player: &Player| player.id < 10,
+    ));
+
+    app.inject_insert(player(1, "Alice"));
+    app.update();
+
+    assert_eq!(drain::<InsertMessage<Player>>(&mut app).len(), 1);
+    assert_eq!(drain::<InsertUpdateMessage<Player>>(&mut app).len(), 1);
+}
+
+#[test]
+fn mapped_tables_send_the_projected_rows() {
+    let mut app = app(
+        plugin().add_table_mapped(RemoteTables::player, |player: &Player| {
+            PlayerName(player.name.clone())
+        }),
+    );
+
+    app.inject_insert(PlayerName("Alice".to_string()))
+        .inject_update(
+            PlayerName("Alice".to_string()),
+            PlayerName("Bob".to_string()),
+        );
+    app.update();
+
+    let inserted = drain::<InsertMessage<PlayerName>>(&mut app);
+    assert_eq!(inserted[0].row, PlayerName("Alice".to_string()));
+    let updated = drain::<UpdateMessage<PlayerName>>(&mut app);
+    assert_eq!(updated[0].new, PlayerName("Bob".to_string()));
+    assert!(
+        !app.world()
+            .contains_resource::<Messages<InsertMessage<Player>>>()
+    );
+}
+
+#[test]
+fn connection_messages_drive_the_connection_state() {
+    let mut app = app(plugin());
+
+    app.inject_connected(Identity::ZERO, "token");
+    app.update();
+    app.update();
+    assert_eq!(
+        *app.world().resource::<State<StdbConnectionState>>().get(),
+        StdbConnectionState::Connected
+    );
+
+    app.inject_disconnected(None);
+    app.update();
+    app.update();
+    assert_eq!(
+        *app.world().resource::<State<StdbConnectionState>>().get(),
+        StdbConnectionState::Disconnected
+    );
+}
+
+#[test]
+fn row_messages_carry_their_event() {
+    let mut app = app(plugin().add_table(RemoteTables::player));
+    let reducer = StdbEventInfo::Reducer {
+        reducer: "set_name",
+        caller_identity: Identity::ZERO,
+        caller_connection_id: None,
+        timestamp: Timestamp::UNIX_EPOCH,
+    };
+
+    app.inject_insert(player(1, "Alice"))
+        .set_injected_row_event(StdbEventInfo::SubscribeApplied)
+        .inject_insert(player(2, "Carol"))
+        .set_injected_row_event(reducer.clone())
+        .inject_update(player(1, "Alice"), player(1, "Bob"));
+    app.update();
+
+    let inserted = drain::<InsertMessage<Player>>(&mut app);
+    assert_eq!(inserted[0].event, StdbEventInfo::UnknownTransaction);
+    assert_eq!(inserted[1].event, StdbEventInfo::SubscribeApplied);
+    let updated = drain::<UpdateMessage<Player>>(&mut app);
+    assert_eq!(updated[0].event, reducer);
+}
+
+#[test]
+fn initial_rows_are_told_apart_from_new_rows() {
+    let mut app = app(plugin().add_table(RemoteTables::player));
+
+    app.set_injected_row_event(StdbEventInfo::SubscribeApplied)
+        .inject_insert(player(1, "Alice"))
+        .set_injected_row_event(StdbEventInfo::UnknownTransaction)
+        .inject_insert(player(2, "Carol"));
+    app.update();
+
+    let inserted = drain::<InsertMessage<Player>>(&mut app);
+    assert!(inserted[0].is_initial());
+    assert!(!inserted[1].is_initial());
+    let inserted_updated = drain::<InsertUpdateMessage<Player>>(&mut app);
+    assert!(inserted_updated[0].is_initial());
+    assert!(!inserted_updated[1].is_initial());
+}
+
+#[test]
+fn a_lost_connection_schedules_a_reconnection() {
+    let mut app = app(plugin().with_reconnect(
+        ReconnectPolicy::default()
+            .with_initial_delay(Duration::from_secs(10))
+            .with_jitter_source(|| 0.5),
+    ));
+
+    app.inject_connected(Identity::ZERO, "token");
+    app.update();
+    app.inject_disconnected(None);
+    app.update();
+
+    // The disconnection is seen in the frame it is received
+    let reconnecting = drain::<StdbReconnectingMessage>(&mut app);
+    assert_eq!(reconnecting.len(), 1);
+    assert_eq!(reconnecting[0].attempt, 1);
+    assert_eq!(reconnecting[0].delay, Duration::from_secs(10));
+    assert_eq!(
+        *app.world().resource::<State<StdbConnectionState>>().get(),
+        StdbConnectionState::Reconnecting
+    );
+}
+
+#[derive(Resource, Default)]
+struct Observed(Vec<String>);
+
+#[test]
+fn row_events_are_triggered_for_observers() {
+    let mut app =
+        app(plugin().add_partial_table(RemoteTables::player, TableMessages::all().with_triggers()));
+    app.init_resource::<Observed>().add_observer(
+        |event: On<RowInserted<Player>>, mut observed: ResMut<Observed>| {
+            observed.0.push(event.row.name.clone());
+        },
+    );
+
+    app.inject_insert(player(1, "Alice"));
+    app.update();
+
+    assert_eq!(app.world().resource::<Observed>().0, vec!["Alice"]);
+}
+
+#[test]
+fn row_events_are_triggered_for_every_registration_of_a_table() {
+    let inserts = TableMessages {
+        insert: true,
+        ..Default::default()
+    };
+    let deletes = TableMessages {
+        delete: true,
+        ..Default::default()
+    };
+    let mut app = app(plugin()
+        .add_partial_table(RemoteTables::player, inserts.with_triggers())
+        .add_partial_table(RemoteTables::player, deletes.with_triggers()));
+    app.init_resource::<Observed>()
+        .add_observer(
+            |event: On<RowInserted<Player>>, mut observed: ResMut<Observed>| {
+                observed.0.push(format!("inserted {}", event.row.name));
+            },
+        )
+        .add_observer(
+            |event: On<RowDeleted<Player>>, mut observed: ResMut<Observed>| {
+                observed.0.push(format!("deleted {}", event.row.name));
+            },
+        );
+
+    app.inject_insert(player(1, "Alice"));
+    app.update();
+    app.inject_delete(player(1, "Alice"));
+    app.update();
+
+    assert_eq!(
+        app.world().resource::<Observed>().0,
+        vec!["inserted Alice", "deleted Alice"]
+    );
+}
+
+#[derive(Component)]
+struct Mirrored;
+
+impl From<Player> for Mirrored {
+    fn from(_: Player) -> Self {
+        Mirrored
+    }
+}
+
+#[test]
+#[should_panic(expected = "already mirrored into entities")]
+fn tables_are_mirrored_into_entities_only_once() {
+    let _app = app(plugin()
+        .add_table_as_bundles::<PlayerName, _, _, _>(RemoteTables::player, |player: &Player| {
+            player.id
+        })
+        .add_table_as_bundles::<Mirrored, _, _, _>(RemoteTables::player, |player: &Player| {
+            player.id
+        }));
+}
+
+#[test]
+fn rows_are_mirrored_into_entities() {
+    let mut app = app(plugin()
+        .add_table_as_bundles::<PlayerName, _, _, _>(RemoteTables::player, |player: &Player| {
+            player.id
+        }));
+    app.init_resource::<Observed>().add_observer(
+        |event: On<EntityRowInserted<Player>>, mut observed: ResMut<Observed>| {
+            observed.0.push(format!("{}", event.row.id));
+        },
+    );
+
+    app.inject_insert(player(1, "Alice"));
+    app.update();
+
+    let entity = app
+        .world()
+        .resource::<TableEntities<Player, u64>>()
+        .get(&1)
+        .expect("the row should be mirrored");
+    assert_eq!(
+        app.world().get::<PlayerName>(entity),
+        Some(&PlayerName("Alice".to_string()))
+    );
+    assert_eq!(app.world().resource::<Observed>().0, vec!["1"]);
+
+    app.inject_update(player(1, "Alice"), player(1, "Bob"));
+    app.update();
+    assert_eq!(
+        app.world().get::<PlayerName>(entity),
+        Some(&PlayerName("Bob".to_string()))
+    );
+
+    app.inject_delete(player(1, "Bob"));
+    app.update();
+    assert!(app.world().get_entity(entity).is_err());
+    assert!(
+        app.world()
+            .resource::<TableEntities<Player, u64>>()
+            .is_empty()
+    );
+}
+
+#[test]
+fn transactions_are_read_as_messages() {
+    let mut app = app(plugin().add_table(RemoteTables::player).with_transactions());
+
+    app.inject_transaction(
+        TransactionMessage::<Reducer>::new(None)
+            .with_insert(player(2, "Carol"))
+            .with_update(player(1, "Alice"), player(1, "Bob"))
+            .with_delete(player(3, "Dave"))
+            .with_insert(7u32),
+    );
+    app.update();
+
+    let transactions = drain::<TransactionMessage<Reducer>>(&mut app);
+    assert_eq!(transactions.len(), 1);
+    let transaction = &transactions[0];
+    assert!(transaction.reducer_event.is_none());
+    assert_eq!(transaction.len(), 4);
+    assert_eq!(
+        transaction.inserts::<Player>().collect::<Vec<_>>(),
+        vec![&player(2, "Carol")]
+    );
+    assert_eq!(
+        transaction.updates::<Player>().collect::<Vec<_>>(),
+        vec![(&player(1, "Alice"), &player(1, "Bob"))]
+    );
+    assert_eq!(
+        transaction.deletes::<Player>().collect::<Vec<_>>(),
+        vec![&player(3, "Dave")]
+    );
+    assert_eq!(transaction.inserts::<u32>().collect::<Vec<_>>(), vec![&7]);
+}
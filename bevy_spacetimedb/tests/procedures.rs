@@ -0,0 +1,82 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy_spacetimedb::{
+    ProcedureResultMessage, ReadProcedureMessage, RegisterProcedureMessage, StdbPlugin,
+    injection::StdbMessageInjectionExt,
+};
+use common::{DbConnection, RemoteModule, UNREACHABLE_URI};
+use spacetimedb_sdk::__codegen::InternalError;
+
+#[derive(Debug, RegisterProcedureMessage)]
+#[stdb(module = "common")]
+struct Echo {
+    message: String,
+    result: Result<String, InternalError>,
+}
+
+#[derive(Resource, Default)]
+struct Echoed(Vec<(String, Option<String>)>);
+
+fn read_echoes(mut messages: ReadProcedureMessage<Echo>, mut echoed: ResMut<Echoed>) {
+    for message in messages.read() {
+        let echo = &message.result;
+        echoed
+            .0
+            .push((echo.message.clone(), echo.result.as_ref().ok().cloned()));
+    }
+}
+
+fn app(delayed_connect: bool) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .add_plugins(
+            StdbPlugin::<DbConnection, RemoteModule>::default()
+                .with_uri(UNREACHABLE_URI)
+                .with_module_name("test")
+                .with_run_fn(DbConnection::run_threaded)
+                .with_delayed_connect(delayed_connect)
+                .add_procedure::<Echo>(),
+        )
+        .init_resource::<Echoed>()
+        .add_systems(Update, read_echoes);
+    app
+}
+
+#[test]
+fn procedures_are_registered_when_connecting_at_startup() {
+    let mut app = app(false);
+    app.update();
+
+    assert!(
+        app.world()
+            .contains_resource::<Messages<ProcedureResultMessage<Echo>>>()
+    );
+}
+
+#[test]
+fn procedures_are_registered_with_delayed_connect() {
+    let mut app = app(true);
+    app.update();
+
+    assert!(
+        app.world()
+            .contains_resource::<Messages<ProcedureResultMessage<Echo>>>()
+    );
+}
+
+#[test]
+fn procedure_results_are_read_as_messages() {
+    let mut app = app(true);
+    app.inject_procedure_result(Echo {
+        message: "hello".to_string(),
+        result: Ok("hello".to_string()),
+    });
+    app.update();
+
+    assert_eq!(
+        app.world().resource::<Echoed>().0,
+        vec![("hello".to_string(), Some("hello".to_string()))]
+    );
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy_spacetimedb::{
+    ReadReducerMessage, ReducerResultMessage, RegisterReducerMessage, RegisterableReducerMessage,
+    StdbPlugin,
+};
+use common::UNREACHABLE_URI;
+use spacetimedb_sdk::ReducerEvent;
+
+#[derive(Debug, RegisterReducerMessage)]
+#[stdb(module = "common")]
+struct SetName {
+    event: ReducerEvent<common::Reducer>,
+    name: String,
+}
+
+mod overridden {
+    use super::*;
+
+    #[derive(Debug, RegisterReducerMessage)]
+    #[stdb(
+        connection = "common::DbConnection",
+        remote_module = "common::RemoteModule",
+        reducers = "common::RemoteReducers"
+    )]
+    pub struct SetName {
+        pub event: ReducerEvent<common::Reducer>,
+        pub name: String,
+    }
+}
+
+fn log_names(
+    mut names: ReadReducerMessage<SetName>,
+    mut overridden_names: ReadReducerMessage<overridden::SetName>,
+) {
+    for message in names.read() {
+        info!("{:?}: {}", message.result.event.status, message.result.name);
+    }
+    for message in overridden_names.read() {
+        info!("{:?}: {}", message.result.event.status, message.result.name);
+    }
+}
+
+fn assert_registerable<
+    E: RegisterableReducerMessage<common::DbConnection, common::RemoteModule>,
+>() {
+}
+
+#[test]
+fn reducers_can_use_module_paths() {
+    assert_registerable::<SetName>();
+    assert_registerable::<overridden::SetName>();
+}
+
+#[test]
+fn reducers_from_a_module_path_can_be_added_to_the_plugin() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .add_plugins(
+            StdbPlugin::<common::DbConnection, common::RemoteModule>::default()
+                .with_uri(UNREACHABLE_URI)
+                .with_module_name("test")
+                .with_run_fn(common::DbConnection::run_threaded)
+                .add_reducer::<SetName>()
+                .add_reducer::<overridden::SetName>(),
+        )
+        .add_systems(
+            Update,
+            log_names.run_if(resource_exists::<Messages<ReducerResultMessage<SetName>>>),
+        );
+    app.update();
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy_spacetimedb::{
+    StdbConnectRequest, StdbConnection, StdbPlugin, StdbSubscription, StdbSubscriptionState,
+    StdbSubscriptions, SubscriptionAppliedMessage, SubscriptionErrorMessage,
+    injection::StdbMessageInjectionExt,
+};
+use common::{DbConnection, RemoteModule, UNREACHABLE_URI, silent_host};
+use spacetimedb_sdk::Error;
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin)).add_plugins(
+        StdbPlugin::<DbConnection, RemoteModule>::default()
+            .with_uri(UNREACHABLE_URI)
+            .with_module_name("test")
+            .with_run_fn(DbConnection::run_threaded)
+            .with_delayed_connect(true)
+            .with_subscriptions([
+                "SELECT * FROM player",
+                "SELECT * FROM player",
+                "SELECT * FROM lobby",
+            ]),
+    );
+    app
+}
+
+fn queries(app: &App) -> Vec<String> {
+    app.world()
+        .resource::<StdbSubscriptions>()
+        .iter()
+        .map(str::to_string)
+        .collect()
+}
+
+fn drain<T: Message>(app: &mut App) -> Vec<T> {
+    app.world_mut()
+        .resource_mut::<Messages<T>>()
+        .drain()
+        .collect()
+}
+
+#[test]
+fn subscriptions_can_be_changed_without_a_connection() {
+    let mut app = app();
+    app.update();
+    assert_eq!(
+        queries(&app),
+        vec!["SELECT * FROM player", "SELECT * FROM lobby"]
+    );
+
+    let mut subscriptions = app.world_mut().resource_mut::<StdbSubscriptions>();
+    assert!(subscriptions.remove("SELECT * FROM lobby"));
+    assert!(!subscriptions.remove("SELECT * FROM lobby"));
+    assert!(subscriptions.add("SELECT * FROM match"));
+    assert!(!subscriptions.add("SELECT * FROM player"));
+    app.update();
+
+    assert_eq!(
+        queries(&app),
+        vec!["SELECT * FROM player", "SELECT * FROM match"]
+    );
+}
+
+/// Opens a connection that the host never identifies.
+fn connect_to_silent_host(app: &mut App) {
+    app.world_mut()
+        .write_message(StdbConnectRequest::new().with_uri(silent_host()));
+    app.update();
+    app.update();
+    let connection = app.world().resource::<StdbConnection<DbConnection>>();
+    assert!(connection.try_connection_id().is_none());
+}
+
+#[test]
+fn subscriptions_wait_for_the_connection_to_be_identified() {
+    let mut app = app();
+    connect_to_silent_host(&mut app);
+
+    app.world_mut()
+        .resource_mut::<StdbSubscriptions>()
+        .add("SELECT * FROM match");
+    app.update();
+
+    assert!(drain::<SubscriptionAppliedMessage>(&mut app).is_empty());
+    assert!(drain::<SubscriptionErrorMessage>(&mut app).is_empty());
+}
+
+#[test]
+fn subscription_results_are_read_as_messages() {
+    let mut app = app();
+    app.inject_subscription_applied("SELECT * FROM player")
+        .inject_subscription_error(
+            "SELECT * FROM lobby",
+            Error::SubscriptionError {
+                error: "no such table".to_string(),
+            },
+        );
+    app.update();
+
+    let applied = drain::<SubscriptionAppliedMessage>(&mut app);
+    assert_eq!(applied.len(), 1);
+    assert_eq!(applied[0].query, "SELECT * FROM player");
+    let errors = drain::<SubscriptionErrorMessage>(&mut app);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].query, "SELECT * FROM lobby");
+}
+
+#[test]
+fn subscription_components_stay_pending_without_a_connection() {
+    let mut app = app();
+    let entity = app
+        .world_mut()
+        .spawn(StdbSubscription::new([
+            "SELECT * FROM tile WHERE chunk = 1",
+        ]))
+        .id();
+    app.update();
+
+    let subscription = app.world().get::<StdbSubscription>(entity).unwrap();
+    assert_eq!(
+        subscription.queries(),
+        ["SELECT * FROM tile WHERE chunk = 1"]
+    );
+    assert_eq!(*subscription.state(), StdbSubscriptionState::Pending);
+
+    app.world_mut().despawn(entity);
+    app.update();
+}
+
+#[test]
+fn subscription_components_wait_for_the_connection_to_be_identified() {
+    let mut app = app();
+    connect_to_silent_host(&mut app);
+
+    let entity = app
+        .world_mut()
+        .spawn(StdbSubscription::new([
+            "SELECT * FROM tile WHERE chunk = 1",
+        ]))
+        .id();
+    app.update();
+
+    let subscription = app.world().get::<StdbSubscription>(entity).unwrap();
+    assert_eq!(*subscription.state(), StdbSubscriptionState::Pending);
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy_spacetimedb::{FileTokenStore, StdbConnectRequest, StdbPlugin, TokenStore};
+use common::{DbConnection, RemoteModule, UNREACHABLE_URI};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+/// A token store in memory, recording the modules whose token was loaded.
+#[derive(Clone, Default)]
+struct MemoryTokenStore {
+    tokens: Arc<Mutex<Vec<(String, String)>>>,
+    loaded: Arc<Mutex<Vec<String>>>,
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self, module_name: &str) -> Option<String> {
+        self.loaded.lock().unwrap().push(module_name.to_string());
+        self.tokens
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(module, _)| module == module_name)
+            .map(|(_, token)| token.clone())
+    }
+
+    fn save(&self, module_name: &str, token: &str) {
+        self.clear(module_name);
+        self.tokens
+            .lock()
+            .unwrap()
+            .push((module_name.to_string(), token.to_string()));
+    }
+
+    fn clear(&self, module_name: &str) {
+        self.tokens
+            .lock()
+            .unwrap()
+            .retain(|(module, _)| module != module_name);
+    }
+}
+
+fn app(store: MemoryTokenStore) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin)).add_plugins(
+        StdbPlugin::<DbConnection, RemoteModule>::default()
+            .with_uri(UNREACHABLE_URI)
+            .with_run_fn(DbConnection::run_threaded)
+            .with_delayed_connect(true)
+            .with_token_store(store)
+            .with_clear_token_on_auth_failure(true),
+    );
+    app
+}
+
+#[test]
+fn file_token_store_saves_tokens_per_module() {
+    let dir = std::env::temp_dir().join(format!("bevy_spacetimedb_tokens_{}", std::process::id()));
+    let store = FileTokenStore::new(&dir);
+
+    assert_eq!(store.load("lobby"), None);
+    store.save("lobby", "lobby-token");
+    store.save("match/1", "match-token");
+    assert_eq!(store.load("lobby").as_deref(), Some("lobby-token"));
+    assert_eq!(store.load("match/1").as_deref(), Some("match-token"));
+    assert!(store.path("match/1").starts_with(&dir));
+
+    store.clear("lobby");
+    store.clear("lobby");
+    assert_eq!(store.load("lobby"), None);
+    assert_eq!(store.load("match/1").as_deref(), Some("match-token"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn connections_without_a_token_load_the_stored_one() {
+    let store = MemoryTokenStore::default();
+    let mut app = app(store.clone());
+    app.world_mut()
+        .write_message(StdbConnectRequest::new().with_module_name("lobby"));
+    app.update();
+
+    assert_eq!(*store.loaded.lock().unwrap(), vec!["lobby".to_string()]);
+}
+
+#[test]
+fn connections_with_a_token_do_not_load_the_stored_one() {
+    let store = MemoryTokenStore::default();
+    let mut app = app(store.clone());
+    app.world_mut().write_message(
+        StdbConnectRequest::new()
+            .with_module_name("lobby")
+            .with_token("token"),
+    );
+    app.update();
+
+    assert!(store.loaded.lock().unwrap().is_empty());
+}
+
+#[test]
+fn stored_tokens_are_kept_when_the_host_is_unreachable() {
+    let store = MemoryTokenStore::default();
+    store.save("lobby", "token");
+    let mut app = app(store.clone());
+    app.world_mut()
+        .write_message(StdbConnectRequest::new().with_module_name("lobby"));
+    app.update();
+
+    assert_eq!(store.load("lobby").as_deref(), Some("token"));
+}
+
+#[test]
+fn stored_tokens_are_cleared_when_the_host_rejects_them() {
+    // A host rejecting the WebSocket upgrade of the connection as unauthorized
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let uri = format!("http://{}", listener.local_addr().unwrap());
+    let host = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        while request.read_line(&mut line).unwrap() > 2 {
+            line.clear();
+        }
+        stream
+            .write_all(
+                b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            )
+            .unwrap();
+    });
+
+    let store = MemoryTokenStore::default();
+    store.save("lobby", "expired-token");
+    store.save("match", "match-token");
+    let mut app = app(store.clone());
+    app.world_mut().write_message(
+        StdbConnectRequest::new()
+            .with_uri(uri)
+            .with_module_name("lobby"),
+    );
+    app.update();
+    host.join().unwrap();
+
+    assert_eq!(store.load("lobby"), None);
+    assert_eq!(store.load("match").as_deref(), Some("match-token"));
+}
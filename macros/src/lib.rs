@@ -1,7 +1,13 @@
 use heck::ToSnakeCase;
+use module_paths::ModulePaths;
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident};
+use syn::{
+    Data, DeriveInput, Error, Field, Fields, GenericArgument, Ident, PathArguments, Type,
+    parse_macro_input, punctuated::Punctuated, token::Comma,
+};
+
+mod module_paths;
 
 /// This macro automatically generates the boilerplate code needed to register a reducer
 /// with the `StdbPlugin`.
@@ -12,6 +18,12 @@ use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident};
 /// - All other fields must match the reducer's parameter types and order
 /// - Struct fields must be named (no tuple structs)
 ///
+/// ## Module paths
+///
+/// By default the generated code refers to `DbConnection`, `RemoteModule` and `RemoteReducers`,
+/// which must be in scope. Use `#[stdb(module = "...")]` to point to the generated module instead,
+/// or `connection`, `remote_module` and `reducers` to override a single path.
+///
 /// ## Example
 ///
 ///```no-run
@@ -20,10 +32,27 @@ use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident};
 ///     pub event: ReducerEvent<Reducer>,
 ///     pub name: String,
 /// }
+///
+/// #[derive(RegisterReducerMessage)]
+/// #[stdb(module = "crate::other_bindings")]
+/// pub struct SetScore {
+///     pub event: ReducerEvent<other_bindings::Reducer>,
+///     pub score: u32,
+/// }
 /// ```
-#[proc_macro_derive(RegisterReducerMessage)]
+#[proc_macro_derive(RegisterReducerMessage, attributes(stdb))]
 pub fn register_reducer_message_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    expand_reducer_message(input).unwrap_or_else(|err| err.to_compile_error().into())
+}
+
+fn expand_reducer_message(input: DeriveInput) -> syn::Result<TokenStream> {
+    let ModulePaths {
+        connection,
+        remote_module,
+        reducers,
+        ..
+    } = ModulePaths::from_attributes(&input.attrs)?;
     let struct_name = &input.ident;
     let struct_name_str = struct_name.to_string();
 
@@ -33,41 +62,40 @@ pub fn register_reducer_message_derive(input: TokenStream) -> TokenStream {
         struct_name.span(),
     );
 
-    // Extract named fields
-    let fields = match input.data {
-        Data::Struct(data_struct) => match data_struct.fields {
-            Fields::Named(fields_named) => fields_named.named,
-            _ => panic!("Struct must have named fields"),
-        },
-        _ => panic!("Only structs are supported"),
-    };
-
     // Separate 'event' field from reducer parameters
     let mut event_field = None;
-    let mut param_fields = Vec::new();
     let mut param_idents = Vec::new();
 
-    for field in fields {
-        let field_ident = field.ident.as_ref().expect("Field must have identifier");
+    for field in named_fields(&input)? {
+        let field_ident = field
+            .ident
+            .as_ref()
+            .expect("Named fields have an identifier");
         if field_ident == "event" {
-            if event_field.is_some() {
-                panic!("Duplicate 'event' field");
-            }
             event_field = Some(field);
         } else {
             param_idents.push(field_ident.clone());
-            param_fields.push(field);
         }
     }
 
-    if event_field.is_none() {
-        panic!("Struct must have an 'event' field");
+    let Some(event_field) = event_field else {
+        return Err(Error::new(
+            struct_name.span(),
+            "Struct must have an 'event' field of type ReducerEvent<Reducer>",
+        ));
+    };
+    if !is_reducer_event(&event_field.ty) {
+        return Err(Error::new_spanned(
+            &event_field.ty,
+            "The 'event' field must be of type ReducerEvent<Reducer>",
+        ));
     }
+    let event_type = &event_field.ty;
 
     // Generate the implementation
     let expanded = quote! {
-        impl bevy_spacetimedb::RegisterableReducerMessage<DbConnection, RemoteModule> for #struct_name {
-            fn set_stdb_callback(reducers: &RemoteReducers, sender: std::sync::mpsc::Sender<bevy_spacetimedb::ReducerResultMessage<Self>>) {
+        impl bevy_spacetimedb::RegisterableReducerMessage<#connection, #remote_module> for #struct_name {
+            fn set_stdb_callback(reducers: &#reducers, sender: std::sync::mpsc::Sender<bevy_spacetimedb::ReducerResultMessage<Self>>) {
                 reducers.#function_name(move |ctx, #(#param_idents),*| {
                     sender
                         .send(bevy_spacetimedb::ReducerResultMessage::new(#struct_name {
@@ -77,8 +105,157 @@ pub fn register_reducer_message_derive(input: TokenStream) -> TokenStream {
                         .unwrap();
                 });
             }
+
+            fn reducer_event(&self) -> Option<&#event_type> {
+                Some(&self.event)
+            }
         }
     };
 
-    TokenStream::from(expanded)
+    Ok(TokenStream::from(expanded))
+}
+
+/// This macro automatically generates the boilerplate code needed to register a procedure
+/// with the `StdbPlugin`.
+///
+/// ## Requirements
+///
+/// - The struct must have exactly one field named `result` of type `Result<T, InternalError>`,
+///   where `T` is the procedure's return type
+/// - All other fields must match the procedure's parameter types and order,
+///   they form the tuple passed to `StdbConnection::call_procedure`
+/// - Struct fields must be named (no tuple structs)
+///
+/// ## Module paths
+///
+/// Like [`RegisterReducerMessage`], the generated code refers to `DbConnection`, `RemoteModule` and
+/// `RemoteProcedures` unless `#[stdb(module = "...")]`, `connection`, `remote_module` or `procedures`
+/// are set.
+///
+/// ## Example
+///
+///```no-run
+/// #[derive(RegisterProcedureMessage)]
+/// pub struct GetScore {
+///     pub player_id: u64,
+///     pub result: Result<u32, InternalError>,
+/// }
+///
+/// // stdb.call_procedure::<GetScore>((player_id,));
+/// ```
+#[proc_macro_derive(RegisterProcedureMessage, attributes(stdb))]
+pub fn register_procedure_message_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_procedure_message(input).unwrap_or_else(|err| err.to_compile_error().into())
+}
+
+fn expand_procedure_message(input: DeriveInput) -> syn::Result<TokenStream> {
+    let ModulePaths {
+        connection,
+        remote_module,
+        procedures,
+        ..
+    } = ModulePaths::from_attributes(&input.attrs)?;
+    let struct_name = &input.ident;
+    let struct_name_str = struct_name.to_string();
+
+    // Derive the procedure call name directly from struct name
+    let function_name = Ident::new(
+        &format!("{}_then", struct_name_str.to_snake_case()),
+        struct_name.span(),
+    );
+
+    // Separate 'result' field from procedure parameters
+    let mut has_result_field = false;
+    let mut param_types = Vec::new();
+    let mut param_idents = Vec::new();
+
+    for field in named_fields(&input)? {
+        let field_ident = field
+            .ident
+            .as_ref()
+            .expect("Named fields have an identifier");
+        if field_ident == "result" {
+            has_result_field = true;
+        } else {
+            param_idents.push(field_ident.clone());
+            param_types.push(&field.ty);
+        }
+    }
+
+    if !has_result_field {
+        return Err(Error::new(
+            struct_name.span(),
+            "Struct must have a 'result' field of type Result<T, InternalError>",
+        ));
+    }
+
+    // Generate the implementation
+    let expanded = quote! {
+        impl bevy_spacetimedb::RegisterableProcedureMessage<#connection, #remote_module> for #struct_name {
+            type Args = (#(#param_types,)*);
+
+            fn invoke(procedures: &#procedures, args: Self::Args, sender: std::sync::mpsc::Sender<bevy_spacetimedb::ProcedureResultMessage<Self>>) {
+                let (#(#param_idents,)*) = args;
+                procedures.#function_name(#(#param_idents.clone(),)* move |_ctx, result| {
+                    sender
+                        .send(bevy_spacetimedb::ProcedureResultMessage::new(#struct_name {
+                            #(#param_idents,)*
+                            result,
+                        }))
+                        .unwrap();
+                });
+            }
+        }
+    };
+
+    Ok(TokenStream::from(expanded))
+}
+
+/// Returns the named fields of the derived struct, or an error pointing at what is not supported.
+fn named_fields(input: &DeriveInput) -> syn::Result<&Punctuated<Field, Comma>> {
+    match &input.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(fields_named) => Ok(&fields_named.named),
+            Fields::Unnamed(fields_unnamed) => Err(Error::new_spanned(
+                fields_unnamed,
+                "Struct must have named fields",
+            )),
+            Fields::Unit => Err(Error::new(
+                input.ident.span(),
+                "Struct must have named fields",
+            )),
+        },
+        Data::Enum(data_enum) => Err(Error::new_spanned(
+            data_enum.enum_token,
+            "Only structs are supported",
+        )),
+        Data::Union(data_union) => Err(Error::new_spanned(
+            data_union.union_token,
+            "Only structs are supported",
+        )),
+    }
+}
+
+/// Whether the type is a `ReducerEvent<T>`, optionally behind a path such as `spacetimedb_sdk::ReducerEvent<T>`.
+fn is_reducer_event(ty: &Type) -> bool {
+    let Type::Path(type_path) = ty else {
+        return false;
+    };
+    if type_path.qself.is_some() {
+        return false;
+    }
+    let Some(segment) = type_path.path.segments.last() else {
+        return false;
+    };
+    if segment.ident != "ReducerEvent" {
+        return false;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => {
+            arguments.args.len() == 1
+                && matches!(arguments.args.first(), Some(GenericArgument::Type(_)))
+        }
+        _ => false,
+    }
 }
@@ -0,0 +1,69 @@
+use quote::format_ident;
+use syn::{Attribute, LitStr, Path, parse_quote};
+
+/// Paths to the types of the generated module bindings used by the derived implementations.
+///
+/// They default to the bare type names, which requires the bindings to be glob-imported where
+/// the macro is used. They can be configured with the `#[stdb(...)]` container attribute:
+///
+/// - `module = "crate::module_bindings"` prefixes every type with the given module path
+/// - `connection`, `remote_module`, `reducers` and `procedures` override the path of a single type
+pub(crate) struct ModulePaths {
+    pub(crate) connection: Path,
+    pub(crate) remote_module: Path,
+    pub(crate) reducers: Path,
+    pub(crate) procedures: Path,
+}
+
+impl ModulePaths {
+    /// Reads the paths from the `#[stdb(...)]` attributes of the derived struct.
+    pub(crate) fn from_attributes(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut module: Option<Path> = None;
+        let mut connection: Option<Path> = None;
+        let mut remote_module: Option<Path> = None;
+        let mut reducers: Option<Path> = None;
+        let mut procedures: Option<Path> = None;
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("stdb")) {
+            attr.parse_nested_meta(|meta| {
+                let slot = if meta.path.is_ident("module") {
+                    &mut module
+                } else if meta.path.is_ident("connection") {
+                    &mut connection
+                } else if meta.path.is_ident("remote_module") {
+                    &mut remote_module
+                } else if meta.path.is_ident("reducers") {
+                    &mut reducers
+                } else if meta.path.is_ident("procedures") {
+                    &mut procedures
+                } else {
+                    return Err(meta.error(
+                        "unknown stdb attribute, expected one of `module`, `connection`, `remote_module`, `reducers` or `procedures`",
+                    ));
+                };
+
+                if slot.is_some() {
+                    return Err(meta.error("duplicate stdb attribute"));
+                }
+                let value: LitStr = meta.value()?.parse()?;
+                *slot = Some(value.parse()?);
+                Ok(())
+            })?;
+        }
+
+        let in_module = |name: &str| -> Path {
+            let ident = format_ident!("{}", name);
+            match &module {
+                Some(module) => parse_quote!(#module::#ident),
+                None => parse_quote!(#ident),
+            }
+        };
+
+        Ok(Self {
+            connection: connection.unwrap_or_else(|| in_module("DbConnection")),
+            remote_module: remote_module.unwrap_or_else(|| in_module("RemoteModule")),
+            reducers: reducers.unwrap_or_else(|| in_module("RemoteReducers")),
+            procedures: procedures.unwrap_or_else(|| in_module("RemoteProcedures")),
+        })
+    }
+}
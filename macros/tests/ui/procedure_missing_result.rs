@@ -0,0 +1,8 @@
+use bevy_spacetimedb_macros::RegisterProcedureMessage;
+
+#[derive(RegisterProcedureMessage)]
+struct GetScore {
+    player_id: u64,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use bevy_spacetimedb_macros::RegisterProcedureMessage;
+
+#[derive(RegisterProcedureMessage)]
+struct GetScore(u64);
+
+fn main() {}
@@ -0,0 +1,8 @@
+use bevy_spacetimedb_macros::RegisterReducerMessage;
+
+#[derive(RegisterReducerMessage)]
+enum SetName {
+    Name(String),
+}
+
+fn main() {}
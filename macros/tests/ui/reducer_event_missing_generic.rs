@@ -0,0 +1,11 @@
+use bevy_spacetimedb_macros::RegisterReducerMessage;
+
+struct ReducerEvent;
+
+#[derive(RegisterReducerMessage)]
+struct SetName {
+    event: ReducerEvent,
+    name: String,
+}
+
+fn main() {}
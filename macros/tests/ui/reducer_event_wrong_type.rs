@@ -0,0 +1,9 @@
+use bevy_spacetimedb_macros::RegisterReducerMessage;
+
+#[derive(RegisterReducerMessage)]
+struct SetName {
+    event: String,
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use bevy_spacetimedb_macros::RegisterReducerMessage;
+
+#[derive(RegisterReducerMessage)]
+union SetName {
+    id: u64,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use bevy_spacetimedb_macros::RegisterReducerMessage;
+
+#[derive(RegisterReducerMessage)]
+struct SetName;
+
+fn main() {}
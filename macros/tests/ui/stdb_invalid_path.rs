@@ -0,0 +1,12 @@
+use bevy_spacetimedb_macros::RegisterReducerMessage;
+
+struct ReducerEvent<T>(T);
+
+#[derive(RegisterReducerMessage)]
+#[stdb(module = "crate::module bindings")]
+struct SetName {
+    event: ReducerEvent<()>,
+    name: String,
+}
+
+fn main() {}